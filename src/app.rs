@@ -1,119 +1,818 @@
 use crate::{
-    cli::Cli,
-    ftrace::FtraceNode,
+    bookmarks::{Bookmark, Bookmarks},
+    cli::{Cli, Command},
+    config::Config,
+    ftrace::{FtraceNode, FtraceTree, SymbolFilter, Threshold, Timebase},
     reader::build_ftrace_tree_from_file,
+    session::{Session, SessionError, SessionState},
     symbol::{
         info::SymbolInfo,
-        resolver::{CachedSymbolResolver, SymbolResolver},
+        region::{LoadInfo, TaRegionInfo, TeeInfo},
+        resolver::{CachedSymbolResolver, Symbol, SymbolResolver},
     },
     ui::{
-        components::{Component as _, TraceTreeComponent},
+        components::{
+            BookmarkList, BookmarkListResult, ButterflyComponent, Completion, Component,
+            FlameGraphComponent, NodeDetailsComponent, Prompt, PromptResult, StatsTableComponent,
+            TimelineComponent, TraceInfoComponent, TraceSummary, TraceTreeComponent,
+        },
         event::{Event, EventGenerator},
+        keymap::Action,
     },
 };
 use color_eyre::eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent};
-use ratatui::{DefaultTerminal, crossterm};
+use crossterm::event::{KeyEvent, KeyEventKind};
+use ratatui::{
+    DefaultTerminal, Frame, crossterm,
+    layout::{Constraint, Layout, Rect},
+    text::Line,
+};
 use std::{
     path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
 };
-use tokio::sync::mpsc::UnboundedSender;
-use tui_tree_widget::TreeItem;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+/// Width of the pane with the details of the selected node
+const DETAILS_WIDTH: u16 = 60;
 
 #[derive(Debug)]
 pub struct App {
     args: Cli,
+    config: Arc<Config>,
     stopping: bool,
     event_generator: EventGenerator<AppMsg>,
+    status: String,
+    prompt: Option<Prompt<PromptKind>>,
+    /// Unset if the bookmarks file could not be read, so that it is not
+    /// overwritten
+    bookmarks: Option<Bookmarks>,
+    bookmark_list: Option<BookmarkList>,
+    /// Where the state of the UI is saved on exit, if anywhere
+    session: Option<Session>,
+    show_info: bool,
+    /// Whether the details of the selected node are shown next to the tree
+    show_details: bool,
+    view: View,
+    tree_component: TraceTreeComponent,
+    stats_component: StatsTableComponent,
+    butterfly_component: ButterflyComponent,
+    flame_component: FlameGraphComponent,
+    timeline_component: TimelineComponent,
+    info_component: TraceInfoComponent,
+    details_component: NodeDetailsComponent,
 }
 
 impl App {
-    pub fn new(args: Cli) -> Self {
+    pub fn new(args: Cli, config: Config) -> Self {
+        let mut status = format!(
+            "Press {} to load an ELF file, {} to quit",
+            key_hint(&config, Action::LoadElf),
+            key_hint(&config, Action::Quit),
+        );
+        let bookmarks = match Bookmarks::load(args.trace_path()) {
+            Ok(bookmarks) => Some(bookmarks),
+            Err(e) => {
+                status = e.to_string();
+                None
+            }
+        };
+        let session = match Session::open(args.trace_path()) {
+            Ok(session) => session,
+            Err(e) => {
+                status = e.to_string();
+                None
+            }
+        };
+        let config = Arc::new(config);
+        let mut tree_component = TraceTreeComponent::new(Arc::clone(&config));
+        if let Some(session) = &session
+            && !args.fresh
+        {
+            match session.load() {
+                Ok(Some(state)) => tree_component.restore(state.tree),
+                Ok(None) => (),
+                Err(e) => status = e.to_string(),
+            }
+        }
         Self {
             args,
+            config: Arc::clone(&config),
             stopping: false,
             event_generator: EventGenerator::new(Duration::from_millis(30)),
+            status,
+            prompt: None,
+            bookmarks,
+            bookmark_list: None,
+            session,
+            show_info: false,
+            show_details: false,
+            view: View::Tree,
+            tree_component,
+            stats_component: StatsTableComponent::new(Arc::clone(&config)),
+            butterfly_component: ButterflyComponent::new(Arc::clone(&config)),
+            flame_component: FlameGraphComponent::new(Arc::clone(&config)),
+            timeline_component: TimelineComponent::new(Arc::clone(&config)),
+            info_component: TraceInfoComponent::new(Arc::clone(&config)),
+            details_component: NodeDetailsComponent::new(config),
         }
     }
 
     pub async fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
-        let ftrace_file = self.args.ftrace_path.clone();
-        let sources = self.args.elf.clone();
+        let args = self.args.clone();
+        let config = Arc::clone(&self.config);
         let event_sender = self.event_generator.get_app_event_sender();
-        tokio::spawn(async move { initialize_ftrace(&ftrace_file, sources, event_sender).await });
-        let mut tree_component = TraceTreeComponent::new();
+        let (command_sender, command_receiver) = unbounded_channel();
+        tokio::spawn(async move {
+            let result = trace_worker(&args, &config, command_receiver, event_sender.clone()).await;
+            if let Err(e) = result {
+                let _ = event_sender.send(AppMsg::Status(format!("Error: {}", e)));
+            }
+        });
 
         while !self.stopping {
-            terminal
-                .draw(|frame| {
-                    tree_component.render(frame, frame.area());
-                })
-                .unwrap();
+            terminal.draw(|frame| self.render(frame)).unwrap();
             let event = self.event_generator.poll_next().await;
-            if let &Event::Key(key_event) = &event {
-                self.handle_key_event(key_event);
+            match &event {
+                &Event::Key(key_event) if self.prompt.is_some() => {
+                    self.handle_prompt_key(key_event, &command_sender);
+                    continue;
+                }
+                &Event::Key(key_event) if self.bookmark_list.is_some() => {
+                    self.handle_bookmark_list_key(key_event);
+                    continue;
+                }
+                &Event::Key(key_event) => {
+                    self.handle_key_event(key_event);
+                }
+                Event::Message(AppMsg::Status(status)) => {
+                    self.status = status.clone();
+                }
+                _ => (),
+            }
+            let tree_updated = matches!(event, Event::Message(AppMsg::UpdateTree(_)));
+            match event {
+                Event::Message(_) => {
+                    self.info_component.handle(event.clone());
+                    self.details_component.handle(event.clone());
+                    self.stats_component.handle(event.clone());
+                    self.butterfly_component.handle(event.clone());
+                    self.flame_component.handle(event.clone());
+                    self.timeline_component.handle(event.clone());
+                    self.tree_component.handle(event);
+                }
+                // Input only goes to the view on screen
+                event => self.active_view().handle(event),
+            }
+            if tree_updated {
+                self.sync_bookmarks();
             }
-            tree_component.handle(event);
         }
-
         Ok(())
     }
 
+    /// Save the state of the UI for the next run of the trace
+    pub fn save_session(&self) -> Result<(), SessionError> {
+        match &self.session {
+            Some(session) => session.save(&SessionState {
+                tree: self.tree_component.session(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn active_view(&mut self) -> &mut dyn Component<AppMsg> {
+        match self.view {
+            View::Tree => &mut self.tree_component,
+            View::Stats => &mut self.stats_component,
+            View::Butterfly => &mut self.butterfly_component,
+            View::Flame => &mut self.flame_component,
+            View::Timeline => &mut self.timeline_component,
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let [main_area, status_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+        if self.show_info {
+            let info_height = self.info_component.height().min(main_area.height / 2);
+            let [view_area, info_area] =
+                Layout::vertical([Constraint::Fill(1), Constraint::Length(info_height)])
+                    .areas(main_area);
+            self.render_view(frame, view_area);
+            self.info_component.render(frame, info_area);
+        } else {
+            self.render_view(frame, main_area);
+        }
+        self.render_status(frame, status_area);
+        if let Some(list) = &mut self.bookmark_list {
+            list.render(frame, main_area);
+        }
+    }
+
+    /// Render the active view, with the details of the selected node next to
+    /// the tree if enabled
+    fn render_view(&mut self, frame: &mut Frame, area: Rect) {
+        if self.show_details && self.view == View::Tree {
+            let [view_area, details_area] =
+                Layout::horizontal([Constraint::Fill(1), Constraint::Length(DETAILS_WIDTH)])
+                    .areas(area);
+            self.tree_component.render(frame, view_area);
+            // The selection may change on render to keep it visible
+            self.details_component
+                .select(self.tree_component.selection());
+            self.details_component.render(frame, details_area);
+        } else {
+            self.active_view().render(frame, area);
+        }
+    }
+
+    fn render_status(&self, frame: &mut Frame, area: Rect) {
+        if let Some(prompt) = &self.prompt {
+            let prompt_area = Rect {
+                y: area.bottom().saturating_sub(3),
+                height: 3.min(area.bottom()),
+                ..area
+            };
+            prompt.render(frame, prompt_area);
+        } else {
+            let status = Line::styled(self.status.as_str(), self.config.theme.status_style());
+            if self.view == View::Tree {
+                let sort = Line::styled(
+                    self.tree_component.sort_status(),
+                    self.config.theme.status_style(),
+                );
+                // Side by side, so that a long status is cut rather than
+                // overwritten
+                let [status_area, sort_area] = Layout::horizontal([
+                    Constraint::Fill(1),
+                    Constraint::Length(sort.width() as u16),
+                ])
+                .spacing(1)
+                .areas(area);
+                frame.render_widget(status, status_area);
+                frame.render_widget(sort, sort_area);
+            } else {
+                frame.render_widget(status, area);
+            }
+        }
+    }
+
     fn handle_key_event(&mut self, key_event: KeyEvent) {
-        match key_event.code {
-            KeyCode::Char('q') | KeyCode::Esc => {
+        if key_event.kind == KeyEventKind::Release {
+            return;
+        }
+        match self.config.keymap.action(&key_event) {
+            Some(Action::Quit) => {
                 self.stopping = true;
             }
+            Some(Action::LoadElf) => {
+                self.prompt = Some(
+                    Prompt::new(PromptKind::LoadElf, "Load ELF file or directory")
+                        .with_completion(Completion::Path),
+                );
+            }
+            Some(Action::ToggleInfo) => {
+                self.show_info = !self.show_info;
+            }
+            Some(Action::ToggleDetails) => {
+                self.show_details = !self.show_details;
+            }
+            Some(Action::NextView) => {
+                self.view = self.view.next();
+            }
+            Some(Action::Butterfly) => {
+                let name = match self.view {
+                    View::Tree => self
+                        .tree_component
+                        .selected_node()
+                        .map(|node| node.function()),
+                    View::Stats => self.stats_component.selected().map(str::to_string),
+                    View::Butterfly | View::Flame | View::Timeline => None,
+                };
+                if let Some(name) = name {
+                    self.butterfly_component.show(&name);
+                    self.view = View::Butterfly;
+                }
+            }
+            Some(Action::ShowInTree) => {
+                let path = match self.view {
+                    View::Flame => self.flame_component.selected_path(),
+                    View::Timeline => self.timeline_component.selected_path(),
+                    _ => None,
+                };
+                if let Some(path) = path {
+                    self.tree_component.select_node(path);
+                    self.view = View::Tree;
+                }
+            }
+            Some(Action::Search) if self.view == View::Stats => {
+                self.prompt = Some(
+                    Prompt::new(PromptKind::FilterStats, "Filter functions")
+                        .with_input(self.stats_component.filter()),
+                );
+            }
+            Some(Action::Search) if self.view == View::Tree => {
+                self.prompt = Some(
+                    Prompt::new(PromptKind::SearchTree, "Search symbols and addresses")
+                        .with_input(self.tree_component.search()),
+                );
+            }
+            Some(Action::SetThreshold) if self.view == View::Tree => {
+                let threshold = self.tree_component.threshold();
+                let input = threshold.map(|t| t.to_string()).unwrap_or_default();
+                self.prompt = Some(
+                    Prompt::new(
+                        PromptKind::Threshold(threshold),
+                        "Hide calls below (e.g. 2us, 1%, 1% of root)",
+                    )
+                    .with_input(input),
+                );
+            }
+            Some(Action::Filter) if self.view == View::Tree => {
+                self.prompt = Some(
+                    Prompt::new(
+                        PromptKind::SymbolFilter,
+                        "Filter symbols (e.g. +invoke -^memcpy$ -__utee_.*)",
+                    )
+                    .with_input(self.tree_component.filter().to_string()),
+                );
+            }
+            Some(Action::Bookmark) if self.view == View::Tree => {
+                let (Some(bookmarks), Some(tree)) = (&self.bookmarks, self.tree_component.tree())
+                else {
+                    return;
+                };
+                match self.tree_component.selected_call() {
+                    Some(path) => {
+                        let note = bookmarks
+                            .find(tree, &path)
+                            .and_then(|idx| bookmarks.list().get(idx))
+                            .map(|bookmark| bookmark.note.clone())
+                            .unwrap_or_default();
+                        self.prompt = Some(
+                            Prompt::new(PromptKind::Bookmark, "Bookmark note (optional)")
+                                .with_input(note),
+                        );
+                    }
+                    None => {
+                        self.status =
+                            "Only calls can be bookmarked, not aggregated nodes".to_string();
+                    }
+                }
+            }
+            Some(Action::Bookmarks) => {
+                self.bookmark_list = Some(BookmarkList::new(
+                    Arc::clone(&self.config),
+                    self.bookmark_entries(),
+                ));
+            }
+            Some(Action::Columns) if self.view == View::Tree => {
+                let columns: Vec<_> = self
+                    .tree_component
+                    .columns()
+                    .iter()
+                    .map(|column| column.to_string())
+                    .collect();
+                self.prompt = Some(
+                    Prompt::new(
+                        PromptKind::TreeColumns,
+                        "Columns in order (total self percent root calls address)",
+                    )
+                    .with_input(columns.join(" ")),
+                );
+            }
             _ => {}
         }
     }
+
+    fn handle_prompt_key(
+        &mut self,
+        key_event: KeyEvent,
+        command_sender: &UnboundedSender<TraceCommand>,
+    ) {
+        let Some(prompt) = &mut self.prompt else {
+            return;
+        };
+        match prompt.handle_key(key_event) {
+            PromptResult::Pending => {
+                // Filter while typing
+                match prompt.kind() {
+                    PromptKind::FilterStats => {
+                        self.stats_component.set_filter(prompt.input());
+                    }
+                    PromptKind::SearchTree => {
+                        self.tree_component.set_search(prompt.input());
+                    }
+                    // Apply the threshold as soon as it is valid
+                    PromptKind::Threshold(_) => {
+                        if let Ok(threshold) = prompt.input().parse() {
+                            self.tree_component.set_threshold(Some(threshold));
+                        }
+                    }
+                    PromptKind::LoadElf
+                    | PromptKind::SymbolFilter
+                    | PromptKind::TreeColumns
+                    | PromptKind::Bookmark => (),
+                }
+            }
+            PromptResult::Cancel => {
+                match prompt.kind() {
+                    PromptKind::FilterStats => {
+                        self.stats_component.set_filter("");
+                    }
+                    PromptKind::SearchTree => {
+                        self.tree_component.set_search("");
+                    }
+                    PromptKind::Threshold(previous) => {
+                        self.tree_component.set_threshold(previous);
+                    }
+                    PromptKind::LoadElf
+                    | PromptKind::SymbolFilter
+                    | PromptKind::TreeColumns
+                    | PromptKind::Bookmark => (),
+                }
+                self.prompt = None;
+            }
+            PromptResult::Submit(input) => {
+                let kind = prompt.kind();
+                self.prompt = None;
+                match kind {
+                    PromptKind::LoadElf => {
+                        if !input.is_empty() {
+                            let _ = command_sender.send(TraceCommand::AddSource(input.into()));
+                        }
+                    }
+                    PromptKind::FilterStats => {
+                        self.stats_component.set_filter(&input);
+                    }
+                    PromptKind::SearchTree => {
+                        self.tree_component.set_search(&input);
+                    }
+                    PromptKind::SymbolFilter => match input.parse::<SymbolFilter>() {
+                        Ok(filter) => self.tree_component.set_filter(filter),
+                        Err(e) => self.status = e.to_string(),
+                    },
+                    PromptKind::Bookmark => self.add_bookmark(input),
+                    PromptKind::TreeColumns => {
+                        match input.split_whitespace().map(str::parse).collect() {
+                            Ok(columns) => self.tree_component.set_columns(columns),
+                            Err(e) => self.status = format!("Invalid columns: {}", e),
+                        }
+                    }
+                    PromptKind::Threshold(_) if input.trim().is_empty() => {
+                        self.tree_component.set_threshold(None);
+                    }
+                    PromptKind::Threshold(previous) => match input.parse::<Threshold>() {
+                        Ok(threshold) => self.tree_component.set_threshold(Some(threshold)),
+                        Err(e) => {
+                            self.tree_component.set_threshold(previous);
+                            self.status = e.to_string();
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
+
+impl App {
+    /// Bookmarks and whether their call is found in the trace
+    fn bookmark_entries(&self) -> Vec<(Bookmark, bool)> {
+        let Some(bookmarks) = &self.bookmarks else {
+            return Vec::new();
+        };
+        let tree = self.tree_component.tree();
+        bookmarks
+            .list()
+            .iter()
+            .map(|bookmark| {
+                let found = tree.and_then(|tree| bookmark.resolve(tree)).is_some();
+                (bookmark.clone(), found)
+            })
+            .collect()
+    }
+
+    /// Show the bookmarks in the tree and the bookmark list
+    fn sync_bookmarks(&mut self) {
+        let paths = match (&self.bookmarks, self.tree_component.tree()) {
+            (Some(bookmarks), Some(tree)) => bookmarks
+                .list()
+                .iter()
+                .filter_map(|bookmark| bookmark.resolve(tree))
+                .collect(),
+            _ => Vec::new(),
+        };
+        self.tree_component.set_bookmarks(paths);
+        let entries = self.bookmark_entries();
+        if let Some(list) = &mut self.bookmark_list {
+            list.update(entries);
+        }
+    }
+
+    fn save_bookmarks(&mut self) {
+        if let Some(bookmarks) = &self.bookmarks
+            && let Err(e) = bookmarks.save()
+        {
+            self.status = e.to_string();
+        }
+    }
+
+    /// Bookmark the selected call with `note`
+    fn add_bookmark(&mut self, note: String) {
+        let (Some(bookmarks), Some(tree), Some(path)) = (
+            &mut self.bookmarks,
+            self.tree_component.tree(),
+            self.tree_component.selected_call(),
+        ) else {
+            return;
+        };
+        let Some(bookmark) = Bookmark::new(tree, &path, note) else {
+            return;
+        };
+        self.status = format!(
+            "Bookmarked {} in {}",
+            bookmark.name(),
+            bookmarks.path().display()
+        );
+        bookmarks.set(tree, bookmark);
+        self.save_bookmarks();
+        self.sync_bookmarks();
+    }
+
+    fn handle_bookmark_list_key(&mut self, key_event: KeyEvent) {
+        let Some(list) = &mut self.bookmark_list else {
+            return;
+        };
+        match list.handle_key(key_event) {
+            BookmarkListResult::Pending => (),
+            BookmarkListResult::Close => {
+                self.bookmark_list = None;
+            }
+            BookmarkListResult::Jump(idx) => {
+                self.bookmark_list = None;
+                let path = self
+                    .bookmarks
+                    .as_ref()
+                    .and_then(|bookmarks| bookmarks.list().get(idx))
+                    .zip(self.tree_component.tree())
+                    .and_then(|(bookmark, tree)| bookmark.resolve(tree));
+                match path {
+                    Some(path) => {
+                        self.tree_component.select_node(&path);
+                        self.view = View::Tree;
+                    }
+                    None => {
+                        self.status = "The bookmarked call is not in the trace".to_string();
+                    }
+                }
+            }
+            BookmarkListResult::Remove(idx) => {
+                let removed = self
+                    .bookmarks
+                    .as_mut()
+                    .and_then(|bookmarks| bookmarks.remove(idx));
+                if let Some(removed) = removed {
+                    self.status = format!("Removed the bookmark of {}", removed.name());
+                    self.save_bookmarks();
+                    self.sync_bookmarks();
+                }
+            }
+        }
+    }
+}
+
+fn key_hint(config: &Config, action: Action) -> String {
+    config
+        .keymap
+        .keys(action)
+        .first()
+        .map(|key| key.to_string())
+        .unwrap_or_else(|| "<unbound>".to_string())
+}
+
+/// Main views, switched between with [`Action::NextView`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum View {
+    Tree,
+    Stats,
+    Butterfly,
+    Flame,
+    Timeline,
+}
+
+impl View {
+    fn next(self) -> Self {
+        match self {
+            Self::Tree => Self::Stats,
+            Self::Stats => Self::Butterfly,
+            Self::Butterfly => Self::Flame,
+            Self::Flame => Self::Timeline,
+            Self::Timeline => Self::Tree,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PromptKind {
+    LoadElf,
+    FilterStats,
+    SearchTree,
+    SymbolFilter,
+    TreeColumns,
+    /// Note of a bookmark of the selected call
+    Bookmark,
+    /// Threshold of the tree, holding the one to restore on cancel
+    Threshold(Option<Threshold>),
 }
 
 #[derive(Clone, Debug)]
 pub enum AppMsg {
     SetFtraceTitle(String),
-    UpdateTree(Vec<TreeItem<'static, u64>>),
+    UpdateTree(Arc<FtraceTree>),
+    UpdateTraceSummary(Arc<TraceSummary>),
+    /// Trace the shown one is compared with
+    UpdateBaseline(Arc<FtraceTree>),
+    Status(String),
 }
 
-async fn initialize_ftrace(
-    ftrace_file: &Path,
+#[derive(Clone, Debug)]
+pub enum TraceCommand {
+    /// Add an ELF file or directory to the symbol sources and resolve the
+    /// nodes which are still unresolved
+    AddSource(PathBuf),
+}
+
+/// A trace with the symbols of its nodes resolved
+pub struct LoadedTrace {
+    pub tree: FtraceTree,
+    pub symbol_info: SymbolInfo,
+    pub resolver: CachedSymbolResolver,
+}
+
+/// Read a trace and resolve its symbols with the ELF files found in `sources`
+pub async fn load_trace(
+    path: &Path,
+    timebase: Timebase,
     sources: Vec<PathBuf>,
-    event_sender: UnboundedSender<AppMsg>,
-) -> Result<()> {
-    let mut tree = build_ftrace_tree_from_file(ftrace_file).await?;
+) -> Result<LoadedTrace> {
+    let mut tree = build_ftrace_tree_from_file(path, timebase).await?;
     let symbol_info: SymbolInfo = tree.trace_info().parse()?;
     let resolver = SymbolResolver::new(sources);
     let mut resolver = CachedSymbolResolver::new(resolver);
-    for node in tree.children_mut() {
-        recursive_resolve_symbol(&mut resolver, &symbol_info, node).await;
+    resolve_tree_symbols(&mut resolver, &symbol_info, &mut tree).await;
+    Ok(LoadedTrace {
+        tree,
+        symbol_info,
+        resolver,
+    })
+}
+
+async fn trace_worker(
+    args: &Cli,
+    config: &Config,
+    mut command_receiver: UnboundedReceiver<TraceCommand>,
+    event_sender: UnboundedSender<AppMsg>,
+) -> Result<()> {
+    let ftrace_file = args.trace_path();
+    let (new_elf, baseline) = match &args.command {
+        Some(Command::Diff(diff)) => (diff.new_elf.as_slice(), Some(diff)),
+        _ => (&[][..], None),
+    };
+    let LoadedTrace {
+        mut tree,
+        symbol_info,
+        mut resolver,
+    } = load_trace(
+        ftrace_file,
+        config.timebase,
+        config.elf_sources_with(new_elf),
+    )
+    .await?;
+    let title = if symbol_info.title.is_empty() {
+        // Older releases may not print the function graph line
+        ftrace_file.display().to_string()
+    } else {
+        symbol_info.title.clone()
+    };
+    event_sender.send(AppMsg::SetFtraceTitle(title))?;
+    event_sender.send(AppMsg::UpdateTree(Arc::new(tree.clone())))?;
+    let summary = build_trace_summary(&tree, &symbol_info, &mut resolver, config);
+    event_sender.send(AppMsg::UpdateTraceSummary(Arc::new(summary)))?;
+
+    if let Some(diff) = baseline {
+        let base = load_trace(
+            &diff.base,
+            config.timebase,
+            config.elf_sources_with(&diff.base_elf),
+        )
+        .await?;
+        event_sender.send(AppMsg::UpdateBaseline(Arc::new(base.tree)))?;
+        event_sender.send(AppMsg::Status(format!(
+            "Compared with {}",
+            diff.base.display()
+        )))?;
+    }
+
+    while let Some(command) = command_receiver.recv().await {
+        match command {
+            TraceCommand::AddSource(source) => {
+                if !source.exists() {
+                    event_sender.send(AppMsg::Status(format!(
+                        "{}: no such file or directory",
+                        source.display()
+                    )))?;
+                    continue;
+                }
+                let status = format!("Added {}", source.display());
+                resolver.add_source(config.remap_path(&source));
+                let resolved = resolve_tree_symbols(&mut resolver, &symbol_info, &mut tree).await;
+                if resolved > 0 {
+                    event_sender.send(AppMsg::UpdateTree(Arc::new(tree.clone())))?;
+                }
+                let summary = build_trace_summary(&tree, &symbol_info, &mut resolver, config);
+                event_sender.send(AppMsg::UpdateTraceSummary(Arc::new(summary)))?;
+                event_sender.send(AppMsg::Status(format!(
+                    "{}, {} more nodes resolved",
+                    status, resolved
+                )))?;
+            }
+        }
     }
-    let tree_data = TraceTreeComponent::build_tree_data(&tree);
-    event_sender.send(AppMsg::SetFtraceTitle(symbol_info.title.clone()))?;
-    event_sender.send(AppMsg::UpdateTree(tree_data))?;
     Ok(())
 }
 
+fn build_trace_summary(
+    tree: &FtraceTree,
+    symbol_info: &SymbolInfo,
+    resolver: &mut CachedSymbolResolver,
+    config: &Config,
+) -> TraceSummary {
+    let elf_paths = symbol_info
+        .elf_list
+        .values()
+        .map(|elf| {
+            let load_info =
+                LoadInfo::TrustedApp(TaRegionInfo::whole_image(elf.uuid, elf.load_addr));
+            (
+                elf.idx,
+                resolver.elf_path(&load_info).map(Path::to_path_buf),
+            )
+        })
+        .collect();
+    let tee_elf_path = symbol_info.tee_load_addr.and_then(|load_addr| {
+        let load_info = LoadInfo::Tee(TeeInfo { load_addr });
+        resolver.elf_path(&load_info).map(Path::to_path_buf)
+    });
+    TraceSummary::new(
+        tree,
+        symbol_info.clone(),
+        elf_paths,
+        tee_elf_path,
+        config.timebase,
+    )
+}
+
+/// Resolve the symbols of all unresolved nodes in the tree.
+///
+/// Returns the number of newly resolved nodes.
+async fn resolve_tree_symbols(
+    resolver: &mut CachedSymbolResolver,
+    symbol_info: &SymbolInfo,
+    tree: &mut FtraceTree,
+) -> usize {
+    let mut resolved = 0;
+    for node in tree.children_mut() {
+        resolved += recursive_resolve_symbol(resolver, symbol_info, node).await;
+    }
+    resolved
+}
+
 async fn recursive_resolve_symbol(
     resolver: &mut CachedSymbolResolver,
     symbol_info: &SymbolInfo,
     node: &mut FtraceNode,
-) {
-    if let Some(symbol) = resolve_symbol(resolver, symbol_info, node.func()).await {
+) -> usize {
+    let mut resolved = 0;
+    if node.symbol().is_none()
+        && let Some(symbol) = resolve_symbol(resolver, symbol_info, node.func()).await
+    {
         node.set_symbol(symbol);
+        resolved += 1;
     }
 
     for child in node.children_mut() {
-        Box::pin(recursive_resolve_symbol(resolver, symbol_info, child)).await;
+        resolved += Box::pin(recursive_resolve_symbol(resolver, symbol_info, child)).await;
     }
+    resolved
 }
 
 async fn resolve_symbol(
     resolver: &mut CachedSymbolResolver,
     symbol_info: &SymbolInfo,
     addr: u64,
-) -> Option<Arc<String>> {
+) -> Option<Arc<Symbol>> {
     let load_info = symbol_info.find_by_addr(addr)?;
     let reladdr = load_info.calculate_reladdr(addr)?;
     resolver.resolve_symbol(&load_info, reladdr).await
@@ -0,0 +1,302 @@
+use crate::ftrace::{FtraceNode, FtraceTree};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// Appended to the path of a trace to get the path of its bookmarks
+const NOTES_SUFFIX: &str = ".notes.toml";
+
+#[derive(Debug, Error)]
+pub enum BookmarkError {
+    #[error("Failed to read bookmarks {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Failed to parse bookmarks {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("Failed to write bookmarks {}: {source}", path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Failed to serialize bookmarks: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+/// A call marked with a note.
+///
+/// The call is identified by the functions of its callers and their
+/// positions among their siblings, so it is found again when the trace is
+/// reloaded, even if calls were added or removed around it, the TA was
+/// loaded at another address or its ELF file was found since.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Bookmark {
+    /// Functions of the call and its callers, from the top level: their
+    /// symbol, or their address if it is unresolved
+    pub call_path: Vec<String>,
+    /// Traced addresses of the call and its callers, from the top level
+    #[serde(default)]
+    pub addrs: Vec<u64>,
+    /// Child indices of the call and its callers, from the top level
+    pub path: Vec<usize>,
+    #[serde(default)]
+    pub note: String,
+}
+
+impl Bookmark {
+    /// Bookmark the call at `path` of `tree`
+    pub fn new(tree: &FtraceTree, path: &[usize], note: String) -> Option<Self> {
+        let nodes = (1..=path.len())
+            .map(|depth| tree.node_at(&path[..depth]))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            call_path: nodes.iter().map(|node| node.function()).collect(),
+            addrs: nodes.iter().map(|node| node.func()).collect(),
+            path: path.to_vec(),
+            note,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        self.call_path
+            .last()
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    /// Path of the bookmarked call in `tree`.
+    ///
+    /// On every level, the sibling calling the saved function closest to the
+    /// saved position is taken.
+    pub fn resolve(&self, tree: &FtraceTree) -> Option<Vec<usize>> {
+        let mut path = Vec::new();
+        for (depth, (&saved_idx, name)) in self.path.iter().zip(&self.call_path).enumerate() {
+            let addr = self.addrs.get(depth).copied();
+            let siblings: Vec<_> = match tree.node_at(&path) {
+                Some(node) => node.children().collect(),
+                None if path.is_empty() => tree.children().collect(),
+                None => return None,
+            };
+            let (idx, _) = siblings
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| is_call(node, name, addr))
+                .min_by_key(|(idx, _)| idx.abs_diff(saved_idx))?;
+            path.push(idx);
+        }
+        Some(path)
+    }
+}
+
+/// Whether `node` calls the function saved as `name` at `addr`. Symbols
+/// match whatever address the TA was loaded at, and addresses match the
+/// calls of the same trace when either side has no symbol.
+fn is_call(node: &FtraceNode, name: &str, addr: Option<u64>) -> bool {
+    if node.function() == name {
+        return true;
+    }
+    let Some(addr) = addr else {
+        return false;
+    };
+    // Unresolved functions are saved as their address
+    let saved_symbol = name != format!("0x{:016x}", addr);
+    node.func() == addr && (node.symbol().is_none() || !saved_symbol)
+}
+
+/// Content of the bookmarks file
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct NotesFile {
+    bookmark: Vec<Bookmark>,
+}
+
+/// Bookmarks of a trace, saved to a file next to it
+#[derive(Debug)]
+pub struct Bookmarks {
+    path: PathBuf,
+    bookmarks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    /// Load the bookmarks of the trace at `trace`, none if it has no
+    /// bookmarks file yet
+    pub fn load(trace: &Path) -> Result<Self, BookmarkError> {
+        let mut path = trace.as_os_str().to_owned();
+        path.push(NOTES_SUFFIX);
+        let path = PathBuf::from(path);
+        let bookmarks = match fs::read_to_string(&path) {
+            Ok(content) => {
+                let file: NotesFile =
+                    toml::from_str(&content).map_err(|source| BookmarkError::Parse {
+                        path: path.clone(),
+                        source,
+                    })?;
+                file.bookmark
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(source) => return Err(BookmarkError::Read { path, source }),
+        };
+        Ok(Self { path, bookmarks })
+    }
+
+    pub fn save(&self) -> Result<(), BookmarkError> {
+        let file = NotesFile {
+            bookmark: self.bookmarks.clone(),
+        };
+        let content = toml::to_string(&file)?;
+        fs::write(&self.path, content).map_err(|source| BookmarkError::Write {
+            path: self.path.clone(),
+            source,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn list(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    /// Index of the bookmark of the call at `path` of `tree`
+    pub fn find(&self, tree: &FtraceTree, path: &[usize]) -> Option<usize> {
+        self.bookmarks
+            .iter()
+            .position(|bookmark| bookmark.resolve(tree).as_deref() == Some(path))
+    }
+
+    /// Add `bookmark` of a call of `tree`, replacing the one of the same call
+    pub fn set(&mut self, tree: &FtraceTree, bookmark: Bookmark) {
+        match self.find(tree, &bookmark.path) {
+            Some(idx) => self.bookmarks[idx] = bookmark,
+            None => self.bookmarks.push(bookmark),
+        }
+    }
+
+    pub fn remove(&mut self, idx: usize) -> Option<Bookmark> {
+        (idx < self.bookmarks.len()).then(|| self.bookmarks.remove(idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ftrace::test_util::node;
+    use crate::symbol::resolver::Symbol;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_resolve_bookmark() {
+        let tree = FtraceTree::new(
+            String::new(),
+            vec![node(
+                0x10,
+                1000,
+                vec![node(0x20, 1000, vec![]), node(0x30, 1000, vec![])],
+            )],
+        );
+        let bookmark = Bookmark::new(&tree, &[0, 1], "slow".to_string()).unwrap();
+        assert_eq!(bookmark.name(), "0x0000000000000030");
+        assert_eq!(bookmark.resolve(&tree), Some(vec![0, 1]));
+
+        // Still found after a call is added before it
+        let file = NotesFile {
+            bookmark: vec![bookmark],
+        };
+        let file: NotesFile = toml::from_str(&toml::to_string(&file).unwrap()).unwrap();
+        let moved = FtraceTree::new(
+            String::new(),
+            vec![node(
+                0x10,
+                1000,
+                vec![
+                    node(0x20, 1000, vec![]),
+                    node(0x20, 1000, vec![]),
+                    node(0x30, 1000, vec![]),
+                ],
+            )],
+        );
+        assert_eq!(file.bookmark[0].resolve(&moved), Some(vec![0, 2]));
+        assert_eq!(file.bookmark[0].note, "slow");
+
+        let other = FtraceTree::new(String::new(), vec![node(0x10, 1000, vec![])]);
+        assert_eq!(file.bookmark[0].resolve(&other), None);
+    }
+
+    fn resolved(func: u64, name: &str, children: Vec<FtraceNode>) -> FtraceNode {
+        let mut node = node(func, 1000, children);
+        node.set_symbol(Arc::new(Symbol {
+            name: name.to_string(),
+            addr: 0x1000,
+            size: 0x100,
+            offset: func & 0xf,
+            location: None,
+        }));
+        node
+    }
+
+    #[test]
+    fn test_resolve_bookmark_symbols() {
+        let tree = FtraceTree::new(
+            String::new(),
+            vec![node(
+                0x10,
+                1000,
+                vec![node(0x20, 1000, vec![]), node(0x34, 1000, vec![])],
+            )],
+        );
+        let bookmark = Bookmark::new(&tree, &[0, 1], String::new()).unwrap();
+
+        // Found by address once the ELF file is loaded
+        let tree = FtraceTree::new(
+            String::new(),
+            vec![resolved(
+                0x10,
+                "main",
+                vec![
+                    resolved(0x20, "init", vec![]),
+                    resolved(0x34, "work", vec![]),
+                ],
+            )],
+        );
+        assert_eq!(bookmark.resolve(&tree), Some(vec![0, 1]));
+
+        // Found by function, without the offset, in a TA loaded elsewhere
+        let bookmark = Bookmark::new(&tree, &[0, 1], String::new()).unwrap();
+        assert_eq!(bookmark.call_path, ["main", "work"]);
+        let moved = FtraceTree::new(
+            String::new(),
+            vec![resolved(
+                0x110,
+                "main",
+                vec![
+                    resolved(0x130, "work", vec![]),
+                    resolved(0x120, "init", vec![]),
+                ],
+            )],
+        );
+        assert_eq!(bookmark.resolve(&moved), Some(vec![0, 0]));
+
+        // Not found at the same address in another function
+        let other = FtraceTree::new(
+            String::new(),
+            vec![resolved(
+                0x10,
+                "main",
+                vec![resolved(0x34, "other", vec![])],
+            )],
+        );
+        assert_eq!(bookmark.resolve(&other), None);
+    }
+}
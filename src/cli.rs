@@ -1,11 +1,92 @@
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use std::path::{Path, PathBuf};
 
 #[derive(Clone, Debug, Parser)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct Cli {
-    pub ftrace_path: PathBuf,
+    #[command(subcommand)]
+    pub command: Option<Command>,
 
-    #[arg(short, long)]
+    #[arg(required = true)]
+    pub ftrace_path: Option<PathBuf>,
+
+    #[arg(short, long, global = true)]
     pub elf: Vec<PathBuf>,
+
+    /// Frequency in Hz of the counter used to record durations
+    #[arg(short, long, value_name = "HZ", global = true)]
+    pub timebase: Option<u64>,
+
+    /// Use this config file instead of the user and project config files
+    #[arg(short, long, value_name = "FILE", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Only show functions matching this regex, their callees and the calls
+    /// leading to them
+    #[arg(long, value_name = "REGEX", global = true)]
+    pub include: Vec<String>,
+
+    /// Hide functions matching this regex, showing their callees in their place
+    #[arg(long, value_name = "REGEX", global = true)]
+    pub exclude: Vec<String>,
+
+    /// Start with the tree collapsed instead of restoring how it was left on
+    /// the last run with the same trace
+    #[arg(long, global = true)]
+    pub fresh: bool,
+}
+
+impl Cli {
+    /// The trace shown in the TUI
+    pub fn trace_path(&self) -> &Path {
+        match &self.command {
+            Some(Command::Diff(diff)) => &diff.new,
+            _ => self
+                .ftrace_path
+                .as_deref()
+                .expect("The trace path is required without a subcommand"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum Command {
+    /// Compare two traces function by function
+    Diff(DiffArgs),
+    /// Summarize many runs of the same scenario, or compare two groups of runs
+    Runs(RunsArgs),
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct DiffArgs {
+    pub base: PathBuf,
+
+    pub new: PathBuf,
+
+    /// ELF file or directory only used for the base trace
+    #[arg(long, value_name = "PATH")]
+    pub base_elf: Vec<PathBuf>,
+
+    /// ELF file or directory only used for the new trace
+    #[arg(long, value_name = "PATH")]
+    pub new_elf: Vec<PathBuf>,
+
+    /// Match call paths from the top level instead of functions
+    #[arg(short, long)]
+    pub aggregate: bool,
+
+    /// Show the new trace in the TUI, colored by the change of each function
+    #[arg(long)]
+    pub tui: bool,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct RunsArgs {
+    /// Directory or glob pattern of the traces
+    pub runs: String,
+
+    /// Directory or glob pattern of the traces to compare with
+    #[arg(long, value_name = "RUNS")]
+    pub base: Option<String>,
 }
@@ -0,0 +1,302 @@
+use crate::{
+    cli::Cli,
+    ftrace::{FtraceError, SymbolFilter, Timebase},
+    ui::{
+        components::TreeColumn,
+        keymap::{Action, KeyBinding, KeyMap},
+        theme::{Theme, ThemeOverrides},
+    },
+};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+const APP_CONFIG_DIR: &str = "optee_ftrace_viewer";
+const USER_CONFIG_FILE: &str = "config.toml";
+const PROJECT_CONFIG_FILE: &str = ".optee_ftrace_viewer.toml";
+const ELF_PATH_ENV: &str = "OPTEE_FTRACE_ELF_PATH";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Failed to read config file {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Failed to parse config file {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("Invalid timebase: {0} Hz")]
+    InvalidTimebase(u64),
+    #[error(transparent)]
+    Filter(#[from] FtraceError),
+}
+
+/// Settings merged from all configuration sources.
+///
+/// Sources are applied in the following order, later ones taking precedence:
+///
+/// 1. Built-in defaults
+/// 2. User config file (`$XDG_CONFIG_HOME/optee_ftrace_viewer/config.toml`)
+/// 3. Project config file (`.optee_ftrace_viewer.toml` in the current
+///    directory or the nearest parent directory)
+/// 4. `OPTEE_FTRACE_ELF_PATH` environment variable
+/// 5. Command line arguments
+///
+/// Scalar settings are overridden and key bindings are replaced per action.
+/// ELF search paths and path remaps are accumulated, and the ones from the
+/// source with the higher precedence are tried first. Symbol filters are
+/// accumulated too, while the list of tree columns is replaced as a whole.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub elf: Vec<PathBuf>,
+    pub path_remaps: Vec<PathRemap>,
+    pub timebase: Timebase,
+    pub theme: Theme,
+    pub keymap: KeyMap,
+    pub filter: SymbolFilter,
+    /// Columns of the tree view, the default ones if unset
+    pub columns: Option<Vec<TreeColumn>>,
+}
+
+impl Config {
+    pub fn load(args: &Cli) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+
+        let files = match &args.config {
+            Some(path) => vec![path.clone()],
+            None => [user_config_path(), project_config_path()]
+                .into_iter()
+                .flatten()
+                .collect(),
+        };
+        for path in files {
+            let file = ConfigFile::read(&path)?;
+            let base_dir = path.parent().unwrap_or(Path::new("."));
+            config.merge_file(file, base_dir)?;
+        }
+
+        if let Some(paths) = env::var_os(ELF_PATH_ENV) {
+            config.prepend_elf(env::split_paths(&paths).filter(|p| !p.as_os_str().is_empty()));
+        }
+
+        config.prepend_elf(args.elf.iter().cloned());
+        config
+            .filter
+            .extend(SymbolFilter::new(&args.include, &args.exclude)?);
+        if let Some(hz) = args.timebase {
+            config.timebase = Timebase::from_hz(hz).ok_or(ConfigError::InvalidTimebase(hz))?;
+        }
+
+        Ok(config)
+    }
+
+    /// ELF search paths with the path remaps applied, and `extra` searched
+    /// first, e.g. the ones only used for one of the traces being compared
+    pub fn elf_sources_with(&self, extra: &[PathBuf]) -> Vec<PathBuf> {
+        extra
+            .iter()
+            .chain(&self.elf)
+            .map(|path| self.remap_path(path))
+            .collect()
+    }
+
+    /// Apply the first matching path remap to `path`
+    pub fn remap_path(&self, path: &Path) -> PathBuf {
+        self.path_remaps
+            .iter()
+            .find_map(|remap| remap.apply(path))
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+
+    fn merge_file(&mut self, file: ConfigFile, base_dir: &Path) -> Result<(), ConfigError> {
+        let ConfigFile {
+            elf,
+            path_remap,
+            timebase,
+            theme,
+            keybindings,
+            filter,
+            columns,
+        } = file;
+
+        self.prepend_elf(elf.into_iter().map(|path| base_dir.join(path)));
+        let path_remap = path_remap.into_iter().map(|remap| PathRemap {
+            to: base_dir.join(remap.to),
+            ..remap
+        });
+        self.path_remaps.splice(0..0, path_remap);
+        if let Some(hz) = timebase {
+            self.timebase = Timebase::from_hz(hz).ok_or(ConfigError::InvalidTimebase(hz))?;
+        }
+        self.theme.merge(&theme);
+        for (action, keys) in keybindings {
+            self.keymap.bind(action, &keys);
+        }
+        self.filter
+            .extend(SymbolFilter::new(&filter.include, &filter.exclude)?);
+        if columns.is_some() {
+            self.columns = columns;
+        }
+        Ok(())
+    }
+
+    fn prepend_elf(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        self.elf.splice(0..0, paths);
+    }
+}
+
+/// Rewrite paths starting with `from` to start with `to` instead, e.g. to
+/// map paths of the build machine to a local checkout
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathRemap {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+impl PathRemap {
+    pub fn apply(&self, path: &Path) -> Option<PathBuf> {
+        path.strip_prefix(&self.from)
+            .ok()
+            .map(|rest| self.to.join(rest))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct ConfigFile {
+    elf: Vec<PathBuf>,
+    path_remap: Vec<PathRemap>,
+    /// Frequency in Hz of the counter used to record durations
+    timebase: Option<u64>,
+    theme: ThemeOverrides,
+    keybindings: HashMap<Action, Vec<KeyBinding>>,
+    filter: FilterSettings,
+    columns: Option<Vec<TreeColumn>>,
+}
+
+/// Regexes on function names, see [`SymbolFilter`]
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FilterSettings {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl ConfigFile {
+    fn read(path: &Path) -> Result<Self, ConfigError> {
+        let content = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&content).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    let path = dirs::config_dir()?
+        .join(APP_CONFIG_DIR)
+        .join(USER_CONFIG_FILE);
+    path.is_file().then_some(path)
+}
+
+fn project_config_path() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    #[test]
+    fn test_merge_config_files() {
+        let user: ConfigFile = toml::from_str(
+            r#"
+            elf = ["/opt/optee/out"]
+            timebase = 24000000
+
+            [theme]
+            time = "light-red"
+
+            [keybindings]
+            quit = ["ctrl-c"]
+
+            [filter]
+            exclude = ["^memcpy$"]
+            "#,
+        )
+        .unwrap();
+        let project: ConfigFile = toml::from_str(
+            r##"
+            elf = ["build/ta", "/abs/tee.elf"]
+            columns = ["calls", "total", "address"]
+
+            [[path-remap]]
+            from = "/build/optee"
+            to = "optee"
+
+            [theme]
+            ratio = "#00ff00"
+            "##,
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        config.merge_file(user, Path::new("/home/user")).unwrap();
+        config.merge_file(project, Path::new("/work")).unwrap();
+
+        assert_eq!(
+            config.elf,
+            [
+                PathBuf::from("/work/build/ta"),
+                PathBuf::from("/abs/tee.elf"),
+                PathBuf::from("/opt/optee/out"),
+            ]
+        );
+        assert_eq!(config.timebase.hz(), 24_000_000);
+        assert_eq!(config.theme.time, Color::LightRed);
+        assert_eq!(config.theme.ratio, Color::Rgb(0, 255, 0));
+        assert_eq!(config.theme.name, Theme::default().name);
+        assert_eq!(
+            config.keymap.keys(Action::Quit),
+            ["ctrl-c".parse::<KeyBinding>().unwrap()]
+        );
+        assert_eq!(
+            config.remap_path(Path::new("/build/optee/out/tee.elf")),
+            PathBuf::from("/work/optee/out/tee.elf")
+        );
+        assert!(config.filter.excludes("memcpy"));
+        assert!(!config.filter.excludes("memcpy_like"));
+        assert_eq!(
+            config.columns,
+            Some(vec![
+                TreeColumn::Calls,
+                TreeColumn::Total,
+                TreeColumn::Address
+            ])
+        );
+    }
+
+    #[test]
+    fn test_reject_unknown_settings() {
+        assert!(toml::from_str::<ConfigFile>("elf-path = []").is_err());
+        assert!(toml::from_str::<ConfigFile>("[keybindings]\nfly = [\"f\"]").is_err());
+        assert!(toml::from_str::<ConfigFile>("columns = [\"speed\"]").is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{
+    app::load_trace,
+    cli::DiffArgs,
+    config::Config,
+    ftrace::{Change, Delta, call_path_figures, diff, function_figures},
+    utils::{format_duration, format_duration_diff},
+};
+use color_eyre::eyre::Result;
+use std::io::{self, Write as _};
+
+/// Print the change of every function between two traces, the largest
+/// regressions first
+pub async fn diff_main(args: &DiffArgs, config: &Config) -> Result<()> {
+    let base = load_trace(
+        &args.base,
+        config.timebase,
+        config.elf_sources_with(&args.base_elf),
+    )
+    .await?;
+    let new = load_trace(
+        &args.new,
+        config.timebase,
+        config.elf_sources_with(&args.new_elf),
+    )
+    .await?;
+    let deltas = if args.aggregate {
+        diff(call_path_figures(&base.tree), call_path_figures(&new.tree))
+    } else {
+        diff(function_figures(&base.tree), function_figures(&new.tree))
+    };
+
+    let mut out = io::stdout().lock();
+    writeln!(
+        out,
+        "{:>12} {:>8}  {:>10} {:>10}  {:>12} {:>8}  {:>8} {:>8}  {:>15}  {}",
+        "Δ total",
+        "%",
+        "base",
+        "new",
+        "Δ self",
+        "%",
+        "Δ calls",
+        "%",
+        "calls",
+        if args.aggregate {
+            "Call path"
+        } else {
+            "Function"
+        },
+    )?;
+    for delta in &deltas {
+        writeln!(out, "{}", format_delta(delta))?;
+    }
+    Ok(())
+}
+
+fn format_delta(delta: &Delta) -> String {
+    let base = delta.base.unwrap_or_default();
+    let new = delta.new.unwrap_or_default();
+    let calls = format!("{} → {}", base.calls, new.calls);
+    format!(
+        "{:>12} {:>8}  {} {}  {:>12} {:>8}  {:>8} {:>8}  {:>15}  {}",
+        format_duration_diff(delta.total_diff()),
+        format_change(delta.total_change()),
+        format_duration(base.total_time),
+        format_duration(new.total_time),
+        format_duration_diff(delta.self_diff()),
+        format_change(delta.self_change()),
+        format!("{:+}", delta.calls_diff()),
+        format_change(delta.calls_change()),
+        calls,
+        delta.key,
+    )
+}
+
+pub fn format_change(change: Change) -> String {
+    match change {
+        Change::Added => "new".to_string(),
+        Change::Removed => "gone".to_string(),
+        Change::Percent(percent) if percent.is_infinite() => "n/a".to_string(),
+        Change::Percent(percent) => format!("{:+.1}%", percent),
+    }
+}
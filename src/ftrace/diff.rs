@@ -0,0 +1,217 @@
+use crate::ftrace::{FtraceNode, FtraceTree, function_stats};
+use std::{collections::HashMap, time::Duration};
+
+/// Separator of the function names in a call path
+const CALL_PATH_SEPARATOR: &str = ";";
+
+/// Figures of one function or call path in one trace
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Figures {
+    pub calls: usize,
+    pub total_time: Duration,
+    pub self_time: Duration,
+}
+
+/// Relative change of a figure between two traces
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Change {
+    /// Only in the new trace
+    Added,
+    /// Only in the base trace
+    Removed,
+    Percent(f64),
+}
+
+impl Change {
+    /// Change from `base` to `new`, `in_base` and `in_new` telling whether
+    /// the function or call path is in each trace
+    pub fn between(base: Duration, new: Duration, in_base: bool, in_new: bool) -> Self {
+        Self::of_values(base.as_secs_f64(), new.as_secs_f64(), in_base, in_new)
+    }
+
+    fn of_values(base: f64, new: f64, in_base: bool, in_new: bool) -> Self {
+        match (in_base, in_new) {
+            (false, _) => Self::Added,
+            (_, false) => Self::Removed,
+            _ if base == 0.0 => Self::Percent(if new == 0.0 { 0.0 } else { f64::INFINITY }),
+            _ => Self::Percent((new / base - 1.0) * 100.0),
+        }
+    }
+}
+
+/// Figures of one function or call path in both traces
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Delta {
+    /// Function name or call path
+    pub key: String,
+    pub base: Option<Figures>,
+    pub new: Option<Figures>,
+}
+
+impl Delta {
+    fn base_or_zero(&self) -> Figures {
+        self.base.unwrap_or_default()
+    }
+
+    fn new_or_zero(&self) -> Figures {
+        self.new.unwrap_or_default()
+    }
+
+    /// Difference of the total time in nanoseconds, positive if it got slower
+    pub fn total_diff(&self) -> i128 {
+        self.new_or_zero().total_time.as_nanos() as i128
+            - self.base_or_zero().total_time.as_nanos() as i128
+    }
+
+    pub fn self_diff(&self) -> i128 {
+        self.new_or_zero().self_time.as_nanos() as i128
+            - self.base_or_zero().self_time.as_nanos() as i128
+    }
+
+    /// Difference of the number of calls, positive if there are more
+    pub fn calls_diff(&self) -> i64 {
+        self.new_or_zero().calls as i64 - self.base_or_zero().calls as i64
+    }
+
+    pub fn total_change(&self) -> Change {
+        Change::between(
+            self.base_or_zero().total_time,
+            self.new_or_zero().total_time,
+            self.base.is_some(),
+            self.new.is_some(),
+        )
+    }
+
+    pub fn calls_change(&self) -> Change {
+        Change::of_values(
+            self.base_or_zero().calls as f64,
+            self.new_or_zero().calls as f64,
+            self.base.is_some(),
+            self.new.is_some(),
+        )
+    }
+
+    pub fn self_change(&self) -> Change {
+        Change::between(
+            self.base_or_zero().self_time,
+            self.new_or_zero().self_time,
+            self.base.is_some(),
+            self.new.is_some(),
+        )
+    }
+}
+
+/// Figures of all functions, keyed by their name
+pub fn function_figures(tree: &FtraceTree) -> HashMap<String, Figures> {
+    function_stats(tree)
+        .into_iter()
+        .map(|stats| {
+            let figures = Figures {
+                calls: stats.calls,
+                total_time: stats.total_time,
+                self_time: stats.self_time,
+            };
+            (stats.name, figures)
+        })
+        .collect()
+}
+
+/// Figures of all call paths, keyed by the function names from the top level
+/// joined with [`CALL_PATH_SEPARATOR`]
+pub fn call_path_figures(tree: &FtraceTree) -> HashMap<String, Figures> {
+    fn visit(node: &FtraceNode, parent: &str, figures: &mut HashMap<String, Figures>) {
+        let path = call_path(parent, &node.function());
+        figures.insert(
+            path.clone(),
+            Figures {
+                calls: node.calls(),
+                total_time: node.time().unwrap_or_default(),
+                self_time: node.self_time().unwrap_or_default(),
+            },
+        );
+        for child in node.children() {
+            visit(child, &path, figures);
+        }
+    }
+
+    let mut figures = HashMap::new();
+    for node in tree.aggregate().children() {
+        visit(node, "", &mut figures);
+    }
+    figures
+}
+
+/// Call path of the function `name` called from `parent`
+pub fn call_path(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}{}{}", parent, CALL_PATH_SEPARATOR, name)
+    }
+}
+
+/// Match the figures of both traces, the largest regressions first
+pub fn diff(base: HashMap<String, Figures>, mut new: HashMap<String, Figures>) -> Vec<Delta> {
+    let mut deltas: Vec<_> = base
+        .into_iter()
+        .map(|(key, base)| {
+            let new = new.remove(&key);
+            Delta {
+                key,
+                base: Some(base),
+                new,
+            }
+        })
+        .collect();
+    deltas.extend(new.into_iter().map(|(key, new)| Delta {
+        key,
+        base: None,
+        new: Some(new),
+    }));
+    deltas.sort_by(|a, b| {
+        b.total_diff()
+            .cmp(&a.total_diff())
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    deltas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ftrace::test_util::node;
+
+    #[test]
+    fn test_diff_call_paths() {
+        let base = FtraceTree::new(
+            String::new(),
+            vec![node(0x10, 1000, vec![node(0x20, 100, vec![])])],
+        );
+        let new = FtraceTree::new(
+            String::new(),
+            vec![node(
+                0x10,
+                1500,
+                vec![node(0x20, 150, vec![]), node(0x30, 300, vec![])],
+            )],
+        );
+        let deltas = diff(call_path_figures(&base), call_path_figures(&new));
+
+        let keys: Vec<_> = deltas.iter().map(|delta| delta.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "0x0000000000000010",
+                "0x0000000000000010;0x0000000000000030",
+                "0x0000000000000010;0x0000000000000020",
+            ]
+        );
+        assert_eq!(deltas[0].total_diff(), 500);
+        assert_eq!(deltas[0].total_change(), Change::Percent(50.0));
+        assert_eq!(deltas[0].calls_diff(), 0);
+        assert_eq!(deltas[0].calls_change(), Change::Percent(0.0));
+        assert_eq!(deltas[0].self_diff(), 150);
+        assert_eq!(deltas[1].total_change(), Change::Added);
+        assert_eq!(deltas[2].total_change(), Change::Percent(50.0));
+    }
+}
@@ -4,4 +4,10 @@ use thiserror::Error;
 pub enum FtraceError {
     #[error("Invalid ftrace entry")]
     InvalidEntry,
+    #[error(
+        "Invalid threshold `{0}`, expected a duration like `2us` or a percentage like `1%` or `1% of root`"
+    )]
+    Threshold(String),
+    #[error("Invalid symbol filter: {0}")]
+    SymbolFilter(String),
 }
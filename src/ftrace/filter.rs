@@ -0,0 +1,103 @@
+use crate::ftrace::FtraceError;
+use regex::Regex;
+use std::{fmt, str::FromStr};
+
+/// Regular expressions on function names choosing the nodes shown.
+///
+/// Excluded functions are left out and their callees moved up to their
+/// caller. If there is any include pattern, only matching functions, their
+/// callees and the calls leading to them are kept.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl SymbolFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, FtraceError> {
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether the function `name` is shown with its callees, regardless of
+    /// its callers
+    pub fn includes(&self, name: &str) -> bool {
+        self.include.is_empty() || self.include.iter().any(|re| re.is_match(name))
+    }
+
+    pub fn excludes(&self, name: &str) -> bool {
+        self.exclude.iter().any(|re| re.is_match(name))
+    }
+
+    /// Add the patterns of `other`
+    pub fn extend(&mut self, other: SymbolFilter) {
+        self.include.extend(other.include);
+        self.exclude.extend(other.exclude);
+    }
+}
+
+fn compile(patterns: &[String]) -> Result<Vec<Regex>, FtraceError> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|e| {
+                // Syntax errors span several lines to point at the error
+                let message = e.to_string();
+                let reason = message.lines().last().unwrap_or_default();
+                let reason = reason.trim_start_matches("error: ");
+                FtraceError::SymbolFilter(format!("`{}`: {}", pattern, reason))
+            })
+        })
+        .collect()
+}
+
+/// Space separated patterns, the excluded ones prefixed with `-` and the
+/// included ones optionally with `+`
+impl fmt::Display for SymbolFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let include = self.include.iter().map(|re| format!("+{}", re));
+        let exclude = self.exclude.iter().map(|re| format!("-{}", re));
+        let terms: Vec<_> = include.chain(exclude).collect();
+        write!(f, "{}", terms.join(" "))
+    }
+}
+
+impl FromStr for SymbolFilter {
+    type Err = FtraceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        for term in s.split_whitespace() {
+            match term.strip_prefix('-') {
+                Some(pattern) => exclude.push(pattern.to_string()),
+                None => include.push(term.strip_prefix('+').unwrap_or(term).to_string()),
+            }
+        }
+        Self::new(&include, &exclude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_filter() {
+        let filter: SymbolFilter = "+invoke -memcpy -__utee_.*".parse().unwrap();
+        assert!(filter.includes("invoke_command"));
+        assert!(!filter.includes("leaf"));
+        assert!(filter.excludes("__utee_log"));
+        assert!(!filter.excludes("invoke"));
+        assert_eq!(filter.to_string(), "+invoke -memcpy -__utee_.*");
+
+        assert!(SymbolFilter::default().includes("leaf"));
+        assert!("-(".parse::<SymbolFilter>().is_err());
+    }
+}
@@ -1,11 +1,38 @@
+mod diff;
 mod error;
 mod file;
+mod filter;
 mod raw_entry;
+mod runs;
+mod stats;
+mod threshold;
+mod timebase;
 mod tree;
 
+pub use diff::{Change, Delta, Figures, call_path, call_path_figures, diff, function_figures};
 pub use error::FtraceError;
 pub use file::FtraceFile;
+pub use filter::SymbolFilter;
 pub use raw_entry::RawFtrace;
+pub use runs::{RunComparison, RunStats, compare_runs, run_stats};
+pub use stats::{Butterfly, CallEdge, FunctionStats, ROOT_CALLER, function_stats};
+pub use threshold::Threshold;
+pub use timebase::Timebase;
 pub use tree::{FtraceNode, FtraceTree};
 
 pub const MAGIC: &[u8] = b"FTRACE\x00\x01";
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    use super::FtraceNode;
+    use std::time::Duration;
+
+    /// Call of `func` taking `nanos`, with `children`
+    pub(crate) fn node(func: u64, nanos: u64, children: Vec<FtraceNode>) -> FtraceNode {
+        let mut node = FtraceNode::new(1, func, Some(Duration::from_nanos(nanos)));
+        for child in children {
+            node.add_child(child);
+        }
+        node
+    }
+}
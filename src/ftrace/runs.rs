@@ -0,0 +1,302 @@
+use crate::ftrace::{FtraceTree, function_stats, stats::percentile};
+use std::{
+    collections::{BTreeSet, HashMap},
+    f64::consts::PI,
+    time::Duration,
+};
+
+/// Spread of one figure across runs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Distribution {
+    pub median: Duration,
+    pub mean: Duration,
+    /// Sample standard deviation
+    pub stddev: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl Distribution {
+    pub fn new(samples: &[Duration]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let nanos = as_nanos(samples);
+        Self {
+            median: percentile(&sorted, 50),
+            mean: Duration::from_secs_f64(mean(&nanos) / 1e9),
+            stddev: Duration::from_secs_f64(variance(&nanos).sqrt() / 1e9),
+            min: sorted.first().copied().unwrap_or_default(),
+            max: sorted.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Figures of one function across runs of the same scenario
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunStats {
+    pub name: String,
+    /// Number of runs calling the function
+    pub runs: usize,
+    /// Median number of calls per run
+    pub calls: usize,
+    pub total_time: Distribution,
+    pub self_time: Distribution,
+    /// Total time of the function in every run
+    pub total_samples: Vec<Duration>,
+}
+
+/// Figures of one function in every run
+struct RunSamples {
+    calls: Vec<usize>,
+    total_time: Vec<Duration>,
+    self_time: Vec<Duration>,
+}
+
+/// Figures of every function across `trees`, the slowest median first.
+///
+/// A run not calling a function counts as having spent no time in it.
+pub fn run_stats(trees: &[FtraceTree]) -> Vec<RunStats> {
+    let runs = trees.len();
+    let mut samples: HashMap<String, RunSamples> = HashMap::new();
+    for (run, tree) in trees.iter().enumerate() {
+        for stats in function_stats(tree) {
+            let entry = samples.entry(stats.name).or_insert_with(|| RunSamples {
+                calls: vec![0; runs],
+                total_time: vec![Duration::ZERO; runs],
+                self_time: vec![Duration::ZERO; runs],
+            });
+            entry.calls[run] = stats.calls;
+            entry.total_time[run] = stats.total_time;
+            entry.self_time[run] = stats.self_time;
+        }
+    }
+
+    let mut stats: Vec<_> = samples
+        .into_iter()
+        .map(|(name, mut samples)| {
+            samples.calls.sort_unstable();
+            RunStats {
+                name,
+                runs: samples.calls.iter().filter(|&&calls| calls > 0).count(),
+                calls: samples
+                    .calls
+                    .get((runs.max(1) - 1) / 2)
+                    .copied()
+                    .unwrap_or_default(),
+                total_time: Distribution::new(&samples.total_time),
+                self_time: Distribution::new(&samples.self_time),
+                total_samples: samples.total_time,
+            }
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        b.total_time
+            .median
+            .cmp(&a.total_time.median)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    stats
+}
+
+/// Figures of one function in two groups of runs
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunComparison {
+    pub name: String,
+    pub base: RunStats,
+    pub new: RunStats,
+    /// Two-sided p-value of Welch's t-test on the total time, if both groups
+    /// have at least two runs
+    pub p_value: Option<f64>,
+}
+
+impl RunComparison {
+    /// Difference of the median total time in nanoseconds, positive if it
+    /// got slower
+    pub fn median_diff(&self) -> i128 {
+        self.new.total_time.median.as_nanos() as i128
+            - self.base.total_time.median.as_nanos() as i128
+    }
+}
+
+/// Compare every function between two groups of runs, the largest
+/// regressions first
+pub fn compare_runs(base: &[FtraceTree], new: &[FtraceTree]) -> Vec<RunComparison> {
+    let mut base_stats: HashMap<_, _> = run_stats(base)
+        .into_iter()
+        .map(|stats| (stats.name.clone(), stats))
+        .collect();
+    let mut new_stats: HashMap<_, _> = run_stats(new)
+        .into_iter()
+        .map(|stats| (stats.name.clone(), stats))
+        .collect();
+    let names: BTreeSet<_> = base_stats.keys().chain(new_stats.keys()).cloned().collect();
+
+    let mut comparisons: Vec<_> = names
+        .into_iter()
+        .map(|name| {
+            let base = base_stats
+                .remove(&name)
+                .unwrap_or_else(|| absent(&name, base.len()));
+            let new = new_stats
+                .remove(&name)
+                .unwrap_or_else(|| absent(&name, new.len()));
+            let p_value = welch_t_test(
+                &as_nanos(&base.total_samples),
+                &as_nanos(&new.total_samples),
+            );
+            RunComparison {
+                name,
+                base,
+                new,
+                p_value,
+            }
+        })
+        .collect();
+    comparisons.sort_by(|a, b| {
+        b.median_diff()
+            .cmp(&a.median_diff())
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    comparisons
+}
+
+/// Figures of a function called in none of `runs` runs
+fn absent(name: &str, runs: usize) -> RunStats {
+    RunStats {
+        name: name.to_string(),
+        runs: 0,
+        calls: 0,
+        total_time: Distribution::default(),
+        self_time: Distribution::default(),
+        total_samples: vec![Duration::ZERO; runs],
+    }
+}
+
+fn as_nanos(samples: &[Duration]) -> Vec<f64> {
+    samples
+        .iter()
+        .map(|sample| sample.as_nanos() as f64)
+        .collect()
+}
+
+fn mean(samples: &[f64]) -> f64 {
+    samples.iter().sum::<f64>() / samples.len().max(1) as f64
+}
+
+/// Sample variance, zero for less than two samples
+fn variance(samples: &[f64]) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let mean = mean(samples);
+    samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (samples.len() - 1) as f64
+}
+
+/// Two-sided p-value of Welch's t-test for a difference between the means of
+/// `a` and `b`
+fn welch_t_test(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let (n_a, n_b) = (a.len() as f64, b.len() as f64);
+    let (se_a, se_b) = (variance(a) / n_a, variance(b) / n_b);
+    let se = se_a + se_b;
+    let diff = mean(b) - mean(a);
+    if se == 0.0 {
+        // Identical runs, any difference is certain
+        return Some(if diff == 0.0 { 1.0 } else { 0.0 });
+    }
+    let t = diff / se.sqrt();
+    let df = se.powi(2) / (se_a.powi(2) / (n_a - 1.0) + se_b.powi(2) / (n_b - 1.0));
+    Some(incomplete_beta(df / 2.0, 0.5, df / (df + t * t)))
+}
+
+/// Regularized incomplete beta function I_x(a, b)
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges quickly only on this side
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+/// Continued fraction of the incomplete beta function, by Lentz's method
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-30;
+    let not_tiny = |v: f64| if v.abs() < TINY { TINY } else { v };
+
+    let mut c = 1.0;
+    let mut d = 1.0 / not_tiny(1.0 - (a + b) * x / (a + 1.0));
+    let mut h = d;
+    for m in 1..=300 {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / not_tiny(1.0 + even * d);
+        c = not_tiny(1.0 + even / c);
+        h *= d * c;
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / not_tiny(1.0 + odd * d);
+        c = not_tiny(1.0 + odd / c);
+        let step = d * c;
+        h *= step;
+        if (step - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    h
+}
+
+/// Natural logarithm of the gamma function, by the Lanczos approximation
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        return (PI / (PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| {
+            sum + c / (x + i as f64 + 1.0)
+        });
+    0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_welch_t_test() {
+        // One degree of freedom follows the Cauchy distribution
+        assert!((incomplete_beta(0.5, 0.5, 0.5) - 0.5).abs() < 1e-9);
+
+        let a = [10.0, 11.0, 9.0, 10.5, 9.5];
+        let b = [10.2, 10.8, 9.1, 10.4, 9.7];
+        let c = [20.0, 21.0, 19.0, 20.5, 19.5];
+        assert!(welch_t_test(&a, &b).unwrap() > 0.5);
+        assert!(welch_t_test(&a, &c).unwrap() < 0.001);
+        assert_eq!(welch_t_test(&a[..1], &c), None);
+        assert_eq!(welch_t_test(&[1.0, 1.0], &[1.0, 1.0]), Some(1.0));
+    }
+}
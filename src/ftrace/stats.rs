@@ -0,0 +1,242 @@
+use crate::ftrace::{FtraceNode, FtraceTree};
+use std::{collections::HashMap, time::Duration};
+
+/// Timing statistics of all calls to one function
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionStats {
+    pub name: String,
+    pub calls: usize,
+    /// Sum of the total time of all calls.
+    ///
+    /// Recursive calls are counted in full on every level.
+    pub total_time: Duration,
+    pub self_time: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p99: Duration,
+}
+
+impl FunctionStats {
+    fn from_samples(name: String, mut times: Vec<Duration>, self_time: Duration) -> Self {
+        times.sort_unstable();
+        let calls = times.len();
+        let total_time = times.iter().sum();
+        Self {
+            name,
+            calls,
+            total_time,
+            self_time,
+            min: times.first().copied().unwrap_or_default(),
+            max: times.last().copied().unwrap_or_default(),
+            mean: total_time / calls.max(1) as u32,
+            p50: percentile(&times, 50),
+            p99: percentile(&times, 99),
+        }
+    }
+}
+
+/// Collect per function statistics, keyed by [`FtraceNode::function`].
+///
+/// Nodes without a recorded return are skipped.
+pub fn function_stats(tree: &FtraceTree) -> Vec<FunctionStats> {
+    let mut samples: HashMap<String, (Vec<Duration>, Duration)> = HashMap::new();
+    for node in tree.dfs_iter() {
+        let (Some(time), Some(self_time)) = (node.time(), node.self_time()) else {
+            continue;
+        };
+        let entry = samples.entry(node.function()).or_default();
+        entry.0.push(time);
+        entry.1 += self_time;
+    }
+
+    samples
+        .into_iter()
+        .map(|(name, (times, self_time))| FunctionStats::from_samples(name, times, self_time))
+        .collect()
+}
+
+/// Name used as the caller of top level calls
+pub const ROOT_CALLER: &str = "(root)";
+
+/// Calls from one function to another, aggregated
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallEdge {
+    pub name: String,
+    pub calls: usize,
+    /// Total time of the called function over these calls
+    pub total_time: Duration,
+}
+
+/// Callers and callees of one function, aggregated over all of its calls
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Butterfly {
+    pub name: String,
+    pub calls: usize,
+    pub total_time: Duration,
+    pub self_time: Duration,
+    /// Callers, with the time spent in this function when called from them
+    pub callers: Vec<CallEdge>,
+    /// Callees, with the time spent in them when called from this function
+    pub callees: Vec<CallEdge>,
+}
+
+impl Butterfly {
+    pub fn new(tree: &FtraceTree, name: &str) -> Self {
+        let mut butterfly = Self {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let mut callers = HashMap::new();
+        let mut callees = HashMap::new();
+        for node in tree.children() {
+            butterfly.visit(node, ROOT_CALLER, &mut callers, &mut callees);
+        }
+        butterfly.callers = sorted_edges(callers);
+        butterfly.callees = sorted_edges(callees);
+        butterfly
+    }
+
+    fn visit(
+        &mut self,
+        node: &FtraceNode,
+        caller: &str,
+        callers: &mut HashMap<String, (usize, Duration)>,
+        callees: &mut HashMap<String, (usize, Duration)>,
+    ) {
+        let name = node.function();
+        if name == self.name {
+            let time = node.time().unwrap_or_default();
+            self.calls += 1;
+            self.total_time += time;
+            self.self_time += node.self_time().unwrap_or_default();
+
+            let edge = callers.entry(caller.to_string()).or_default();
+            edge.0 += 1;
+            edge.1 += time;
+            for child in node.children() {
+                let edge = callees.entry(child.function()).or_default();
+                edge.0 += 1;
+                edge.1 += child.time().unwrap_or_default();
+            }
+        }
+
+        for child in node.children() {
+            self.visit(child, &name, callers, callees);
+        }
+    }
+}
+
+fn sorted_edges(edges: HashMap<String, (usize, Duration)>) -> Vec<CallEdge> {
+    let mut edges: Vec<_> = edges
+        .into_iter()
+        .map(|(name, (calls, total_time))| CallEdge {
+            name,
+            calls,
+            total_time,
+        })
+        .collect();
+    edges.sort_by(|a, b| {
+        b.total_time
+            .cmp(&a.total_time)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    edges
+}
+
+/// Nearest-rank percentile of sorted samples
+pub fn percentile(sorted: &[Duration], p: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank.min(sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ftrace::FtraceTree;
+    use crate::ftrace::test_util::node;
+
+    #[test]
+    fn test_function_stats() {
+        let tree = FtraceTree::new(
+            String::new(),
+            vec![node(
+                0x10,
+                1000,
+                vec![
+                    node(0x20, 100, vec![]),
+                    node(0x20, 300, vec![node(0x30, 50, vec![])]),
+                ],
+            )],
+        );
+        let mut stats = function_stats(&tree);
+        stats.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(stats.len(), 3);
+        let outer = &stats[0];
+        assert_eq!(outer.calls, 1);
+        assert_eq!(outer.total_time, Duration::from_nanos(1000));
+        assert_eq!(outer.self_time, Duration::from_nanos(600));
+
+        let inner = &stats[1];
+        assert_eq!(inner.name, "0x0000000000000020");
+        assert_eq!(inner.calls, 2);
+        assert_eq!(inner.total_time, Duration::from_nanos(400));
+        assert_eq!(inner.self_time, Duration::from_nanos(350));
+        assert_eq!(inner.min, Duration::from_nanos(100));
+        assert_eq!(inner.max, Duration::from_nanos(300));
+        assert_eq!(inner.mean, Duration::from_nanos(200));
+        assert_eq!(inner.p50, Duration::from_nanos(100));
+        assert_eq!(inner.p99, Duration::from_nanos(300));
+    }
+
+    #[test]
+    fn test_butterfly() {
+        let tree = FtraceTree::new(
+            String::new(),
+            vec![
+                node(
+                    0x10,
+                    1000,
+                    vec![node(0x20, 100, vec![]), node(0x30, 300, vec![])],
+                ),
+                node(0x30, 200, vec![node(0x20, 50, vec![])]),
+            ],
+        );
+        let butterfly = Butterfly::new(&tree, "0x0000000000000030");
+
+        assert_eq!(butterfly.calls, 2);
+        assert_eq!(butterfly.total_time, Duration::from_nanos(500));
+        assert_eq!(butterfly.self_time, Duration::from_nanos(450));
+        let callers: Vec<_> = butterfly
+            .callers
+            .iter()
+            .map(|edge| (edge.name.as_str(), edge.calls, edge.total_time.as_nanos()))
+            .collect();
+        assert_eq!(
+            callers,
+            [("0x0000000000000010", 1, 300), (ROOT_CALLER, 1, 200)]
+        );
+        assert_eq!(
+            butterfly.callees,
+            [CallEdge {
+                name: "0x0000000000000020".to_string(),
+                calls: 1,
+                total_time: Duration::from_nanos(50),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_percentile() {
+        let samples: Vec<_> = (1..=100).map(Duration::from_nanos).collect();
+        assert_eq!(percentile(&samples, 50), Duration::from_nanos(50));
+        assert_eq!(percentile(&samples, 99), Duration::from_nanos(99));
+        assert_eq!(percentile(&samples[..1], 99), Duration::from_nanos(1));
+        assert_eq!(percentile(&[], 50), Duration::ZERO);
+    }
+}
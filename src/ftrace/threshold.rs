@@ -0,0 +1,105 @@
+use crate::{
+    ftrace::FtraceError,
+    utils::{format_duration, parse_duration},
+};
+use std::{fmt, str::FromStr, time::Duration};
+
+/// Smallest total time of the nodes shown
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Threshold {
+    Time(Duration),
+    /// Percentage of the total time of the caller
+    OfParent(f64),
+    /// Percentage of the total time of the trace
+    OfRoot(f64),
+}
+
+impl Threshold {
+    /// Whether a node taking `time` is below the threshold
+    pub fn hides(&self, time: Duration, parent_time: Duration, root_time: Duration) -> bool {
+        let percent_of = |upper: Duration| time.as_secs_f64() / upper.as_secs_f64() * 100.0;
+        match *self {
+            Self::Time(min) => time < min,
+            Self::OfParent(min) => !parent_time.is_zero() && percent_of(parent_time) < min,
+            Self::OfRoot(min) => !root_time.is_zero() && percent_of(root_time) < min,
+        }
+    }
+
+    /// The same kind of threshold, `factor` times as large
+    pub fn scaled(self, factor: f64) -> Self {
+        match self {
+            Self::Time(min) => Self::Time(min.mul_f64(factor)),
+            Self::OfParent(min) => Self::OfParent(min * factor),
+            Self::OfRoot(min) => Self::OfRoot(min * factor),
+        }
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Time(min) => write!(f, "{}", format_duration(*min).trim()),
+            Self::OfParent(min) => write!(f, "{}%", format_percent(*min)),
+            Self::OfRoot(min) => write!(f, "{}% of root", format_percent(*min)),
+        }
+    }
+}
+
+/// Percentage with at most 4 decimals and no trailing zeros, so the noise
+/// of repeated scaling does not show
+fn format_percent(percent: f64) -> String {
+    let percent = format!("{:.4}", percent);
+    percent
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+impl FromStr for Threshold {
+    type Err = FtraceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || FtraceError::Threshold(s.to_string());
+        let percent = |value: &str| {
+            value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite() && *value >= 0.0)
+                .ok_or_else(invalid)
+        };
+        let s = s.trim();
+        if let Some((value, upper)) = s.split_once('%') {
+            match upper.trim() {
+                "" => percent(value).map(Self::OfParent),
+                "root" | "of root" => percent(value).map(Self::OfRoot),
+                _ => Err(invalid()),
+            }
+        } else {
+            parse_duration(s).map(Self::Time).ok_or_else(invalid)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_threshold() {
+        assert_eq!("2us".parse(), Ok(Threshold::Time(Duration::from_micros(2))));
+        assert_eq!("1.5%".parse(), Ok(Threshold::OfParent(1.5)));
+        assert_eq!("3%root".parse(), Ok(Threshold::OfRoot(3.0)));
+        for threshold in [
+            Threshold::Time(Duration::from_nanos(1_500)),
+            Threshold::OfParent(0.5),
+            Threshold::OfRoot(10.0),
+        ] {
+            assert_eq!(threshold.to_string().parse(), Ok(threshold));
+        }
+        assert_eq!(Threshold::OfParent(0.1).scaled(3.0).to_string(), "0.3%");
+        assert_eq!(Threshold::OfRoot(2.0).to_string(), "2% of root");
+        assert!("2 apples".parse::<Threshold>().is_err());
+        assert!("-1%".parse::<Threshold>().is_err());
+    }
+}
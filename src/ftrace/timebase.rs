@@ -0,0 +1,37 @@
+use std::{num::NonZeroU64, time::Duration};
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// Frequency of the counter the durations in a trace are recorded with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Timebase {
+    hz: NonZeroU64,
+}
+
+impl Timebase {
+    pub const NANOS: Self = Self {
+        hz: NonZeroU64::new(NANOS_PER_SEC).unwrap(),
+    };
+
+    pub fn from_hz(hz: u64) -> Option<Self> {
+        NonZeroU64::new(hz).map(|hz| Self { hz })
+    }
+
+    pub fn hz(&self) -> u64 {
+        self.hz.get()
+    }
+
+    pub fn to_duration(self, ticks: u64) -> Duration {
+        if self == Self::NANOS {
+            return Duration::from_nanos(ticks);
+        }
+        let nanos = ticks as u128 * NANOS_PER_SEC as u128 / self.hz.get() as u128;
+        Duration::from_nanos(nanos.try_into().unwrap_or(u64::MAX))
+    }
+}
+
+impl Default for Timebase {
+    fn default() -> Self {
+        Self::NANOS
+    }
+}
@@ -1,5 +1,8 @@
-use crate::ftrace::{FtraceError, RawFtrace};
-use std::{iter::FusedIterator, sync::Arc, time::Duration};
+use crate::{
+    ftrace::{FtraceError, RawFtrace, SymbolFilter, Timebase},
+    symbol::resolver::Symbol,
+};
+use std::{collections::HashMap, iter::FusedIterator, sync::Arc, time::Duration};
 
 #[derive(Clone, Debug)]
 pub struct FtraceTree {
@@ -35,11 +38,234 @@ impl FtraceTree {
         self.children.iter_mut()
     }
 
+    /// Top level calls with their estimated start since the start of the trace
+    pub fn children_with_offsets(&self) -> impl Iterator<Item = (Duration, &FtraceNode)> {
+        with_offsets(&self.children)
+    }
+
+    /// Node reached by following child indices from the top level
+    pub fn node_at(&self, path: &[usize]) -> Option<&FtraceNode> {
+        let (first, rest) = path.split_first()?;
+        rest.iter()
+            .try_fold(self.children.get(*first)?, |node, idx| {
+                node.children.get(*idx)
+            })
+    }
+
     pub fn dfs_iter(&self) -> FtraceDfsIter<'_> {
         FtraceDfsIter {
             stack: vec![self.children.iter()],
         }
     }
+
+    /// Sum of the time of all top level calls
+    pub fn total_time(&self) -> Duration {
+        self.children
+            .iter()
+            .map(|node| node.time().unwrap_or_default())
+            .sum()
+    }
+
+    pub fn max_depth(&self) -> u8 {
+        self.dfs_iter().map(|node| node.depth()).max().unwrap_or(0)
+    }
+
+    /// Build the calling context tree, where all calls with the same path of
+    /// functions from the top level are merged into one node.
+    ///
+    /// Merged nodes keep the order of their first call, and are named after
+    /// the function without an offset if their calls were resolved at
+    /// different ones.
+    pub fn aggregate(&self) -> FtraceTree {
+        Self {
+            trace_info: self.trace_info.clone(),
+            children: aggregate_nodes(&self.children.iter().collect::<Vec<_>>()),
+        }
+    }
+
+    /// Apply `filter`, moving the callees of excluded functions up to their
+    /// caller, whose self time grows by the time of the calls left out
+    pub fn filter(&self, filter: &SymbolFilter) -> FtraceTree {
+        Self {
+            trace_info: self.trace_info.clone(),
+            children: filter_nodes(&self.children, filter, self.top_depth(), false),
+        }
+    }
+
+    /// Path in the tree filtered by `filter` of the node at `path`, or of its
+    /// closest ancestor left in it
+    pub fn filtered_path(&self, filter: &SymbolFilter, path: &[usize]) -> Vec<usize> {
+        let mut filtered = Vec::new();
+        let mut nodes = self.children.as_slice();
+        let mut included = false;
+        // Position at the current level of the callees of excluded calls
+        let mut offset = 0;
+        for &idx in path {
+            let Some(node) = nodes.get(idx) else {
+                break;
+            };
+            let position = offset
+                + nodes[..idx]
+                    .iter()
+                    .map(|sibling| kept_count(sibling, filter, included))
+                    .sum::<usize>();
+            let name = node.function();
+            included |= filter.includes(&name);
+            nodes = &node.children;
+            if filter.excludes(&name) {
+                offset = position;
+                continue;
+            }
+            if kept_count(node, filter, included) == 0 {
+                break;
+            }
+            filtered.push(position);
+            offset = 0;
+        }
+        filtered
+    }
+
+    /// Path in this tree of the node at `filtered_path` of the tree filtered
+    /// by `filter`, the inverse of [`Self::filtered_path`]
+    pub fn unfiltered_path(
+        &self,
+        filter: &SymbolFilter,
+        filtered_path: &[usize],
+    ) -> Option<Vec<usize>> {
+        let mut path = Vec::new();
+        let mut nodes = self.children.as_slice();
+        let mut included = false;
+        for &position in filtered_path {
+            let mut position = position;
+            // Callees of excluded calls take their place, so look for the
+            // position among them too
+            loop {
+                let (idx, node) = nodes.iter().enumerate().find(|(_, node)| {
+                    let kept = kept_count(node, filter, included);
+                    match position.checked_sub(kept) {
+                        Some(rest) => {
+                            position = rest;
+                            false
+                        }
+                        None => true,
+                    }
+                })?;
+                path.push(idx);
+                let name = node.function();
+                included |= filter.includes(&name);
+                nodes = &node.children;
+                if !filter.excludes(&name) {
+                    break;
+                }
+            }
+        }
+        Some(path)
+    }
+
+    fn top_depth(&self) -> u8 {
+        self.children.first().map(|node| node.depth).unwrap_or(0)
+    }
+}
+
+/// Filter `nodes` at `depth`, all of them included if `included` is set
+fn filter_nodes(
+    nodes: &[FtraceNode],
+    filter: &SymbolFilter,
+    depth: u8,
+    included: bool,
+) -> Vec<FtraceNode> {
+    let mut filtered = Vec::new();
+    for node in nodes {
+        let name = node.function();
+        let included = included || filter.includes(&name);
+        if filter.excludes(&name) {
+            filtered.extend(filter_nodes(&node.children, filter, depth, included));
+            continue;
+        }
+        let children = filter_nodes(&node.children, filter, depth.saturating_add(1), included);
+        // Nothing included below either
+        if !included && children.is_empty() {
+            continue;
+        }
+        let mut kept = FtraceNode {
+            symbol: node.symbol.clone(),
+            time: node.time,
+            calls: node.calls,
+            ..FtraceNode::new(depth, node.func, None)
+        };
+        for child in children {
+            kept.add_child(child);
+        }
+        filtered.push(kept);
+    }
+    filtered
+}
+
+/// Number of nodes `node` turns into at its level when filtered, as
+/// [`filter_nodes`] would return without building them
+fn kept_count(node: &FtraceNode, filter: &SymbolFilter, included: bool) -> usize {
+    let name = node.function();
+    let included = included || filter.includes(&name);
+    let mut children = node
+        .children
+        .iter()
+        .map(|child| kept_count(child, filter, included));
+    if filter.excludes(&name) {
+        children.sum()
+    } else if included || children.any(|kept| kept > 0) {
+        1
+    } else {
+        0
+    }
+}
+
+fn aggregate_nodes(nodes: &[&FtraceNode]) -> Vec<FtraceNode> {
+    let mut groups: Vec<Vec<&FtraceNode>> = Vec::new();
+    let mut group_idx: HashMap<String, usize> = HashMap::new();
+    for &node in nodes {
+        let function = node.function();
+        match group_idx.get(&function) {
+            Some(&idx) => groups[idx].push(node),
+            None => {
+                group_idx.insert(function, groups.len());
+                groups.push(vec![node]);
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|group| {
+            let first = group[0];
+            let times: Vec<_> = group.iter().filter_map(|node| node.time).collect();
+            let symbol = match &first.symbol {
+                Some(symbol)
+                    if group
+                        .iter()
+                        .any(|node| node.symbol.as_deref() != Some(symbol)) =>
+                {
+                    Some(Arc::new(Symbol {
+                        offset: 0,
+                        location: None,
+                        ..Symbol::clone(symbol)
+                    }))
+                }
+                symbol => symbol.clone(),
+            };
+            let mut merged = FtraceNode {
+                symbol,
+                // Unfinished calls don't count as zero time
+                time: (!times.is_empty()).then(|| times.iter().sum()),
+                calls: group.iter().map(|node| node.calls).sum(),
+                ..FtraceNode::new(first.depth, first.func, None)
+            };
+            let children: Vec<_> = group.iter().flat_map(|node| &node.children).collect();
+            for child in aggregate_nodes(&children) {
+                merged.add_child(child);
+            }
+            merged
+        })
+        .collect()
 }
 
 #[derive(Clone, Debug)]
@@ -47,9 +273,11 @@ pub struct FtraceNode {
     children: Vec<FtraceNode>,
     depth: u8,
     func: u64,
-    symbol: Option<Arc<String>>,
+    symbol: Option<Arc<Symbol>>,
     time: Option<Duration>,
     children_time: Duration,
+    /// Number of calls merged into this node
+    calls: usize,
 }
 
 impl FtraceNode {
@@ -61,6 +289,7 @@ impl FtraceNode {
             symbol: None,
             time,
             children_time: Duration::ZERO,
+            calls: 1,
         }
     }
 
@@ -72,12 +301,12 @@ impl FtraceNode {
         Ok(Self::new(code.depth(), code.data(), None))
     }
 
-    pub fn end_with(&mut self, code: RawFtrace) -> Result<(), FtraceError> {
+    pub fn end_with(&mut self, code: RawFtrace, timebase: Timebase) -> Result<(), FtraceError> {
         if !code.is_end() {
             return Err(FtraceError::InvalidEntry);
         }
 
-        self.time = Some(Duration::from_nanos(code.data()));
+        self.time = Some(timebase.to_duration(code.data()));
         Ok(())
     }
 
@@ -96,11 +325,28 @@ impl FtraceNode {
         self.func
     }
 
-    pub fn symbol(&self) -> Option<&str> {
-        self.symbol.as_deref().map(|s| s.as_str())
+    pub fn symbol(&self) -> Option<&Symbol> {
+        self.symbol.as_deref()
     }
 
-    pub fn set_symbol(&mut self, symbol: Arc<String>) {
+    /// Symbol of the function, or its address if it is unresolved
+    pub fn name(&self) -> String {
+        match &self.symbol {
+            Some(symbol) => symbol.to_string(),
+            None => format!("0x{:016x}", self.func),
+        }
+    }
+
+    /// Key of the function called, without the offset into it, so that all
+    /// calls of a function share it whatever address they were resolved at
+    pub fn function(&self) -> String {
+        match &self.symbol {
+            Some(symbol) => symbol.name.clone(),
+            None => format!("0x{:016x}", self.func),
+        }
+    }
+
+    pub fn set_symbol(&mut self, symbol: Arc<Symbol>) {
         self.symbol = Some(symbol);
     }
 
@@ -108,6 +354,10 @@ impl FtraceNode {
         self.time
     }
 
+    pub fn calls(&self) -> usize {
+        self.calls
+    }
+
     pub fn self_time(&self) -> Option<Duration> {
         self.time.map(|t| t.saturating_sub(self.children_time()))
     }
@@ -123,6 +373,31 @@ impl FtraceNode {
     pub fn children_mut(&mut self) -> impl Iterator<Item = &mut FtraceNode> {
         self.children.iter_mut()
     }
+
+    /// Number of nodes below this one, at any depth
+    pub fn descendants(&self) -> usize {
+        self.children
+            .iter()
+            .map(|child| 1 + child.descendants())
+            .sum()
+    }
+
+    /// Callees with their estimated start since the start of this call
+    pub fn children_with_offsets(&self) -> impl Iterator<Item = (Duration, &FtraceNode)> {
+        with_offsets(&self.children)
+    }
+}
+
+/// Estimate the start of consecutive calls.
+///
+/// Entries carry no timestamps, so calls are assumed to follow each other
+/// without any gap, and the self time of their caller to come after them.
+fn with_offsets(nodes: &[FtraceNode]) -> impl Iterator<Item = (Duration, &FtraceNode)> {
+    nodes.iter().scan(Duration::ZERO, |offset, node| {
+        let start = *offset;
+        *offset += node.time().unwrap_or_default();
+        Some((start, node))
+    })
 }
 
 pub struct FtraceDfsIter<'a> {
@@ -148,3 +423,145 @@ impl<'a> Iterator for FtraceDfsIter<'a> {
     }
 }
 impl FusedIterator for FtraceDfsIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ftrace::test_util::node;
+
+    #[test]
+    fn test_aggregate() {
+        let tree = FtraceTree::new(
+            String::new(),
+            vec![node(
+                0x10,
+                1000,
+                vec![
+                    node(0x20, 100, vec![node(0x40, 10, vec![])]),
+                    node(0x30, 300, vec![]),
+                    node(0x20, 200, vec![node(0x40, 20, vec![])]),
+                ],
+            )],
+        );
+        let aggregated = tree.aggregate();
+
+        let top: Vec<_> = aggregated.children().collect();
+        assert_eq!(top.len(), 1);
+        let children: Vec<_> = top[0].children().collect();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].func(), 0x20);
+        assert_eq!(children[0].calls(), 2);
+        assert_eq!(children[0].time(), Some(Duration::from_nanos(300)));
+        assert_eq!(children[0].self_time(), Some(Duration::from_nanos(270)));
+        assert_eq!(children[1].func(), 0x30);
+        assert_eq!(children[1].calls(), 1);
+
+        let leaf: Vec<_> = children[0].children().collect();
+        assert_eq!(leaf.len(), 1);
+        assert_eq!(leaf[0].calls(), 2);
+        assert_eq!(leaf[0].time(), Some(Duration::from_nanos(30)));
+    }
+
+    #[test]
+    fn test_filter() {
+        let tree = FtraceTree::new(
+            String::new(),
+            vec![node(
+                0x10,
+                1000,
+                vec![
+                    node(0x20, 100, vec![node(0x40, 10, vec![])]),
+                    node(0x30, 300, vec![node(0x50, 50, vec![])]),
+                ],
+            )],
+        );
+        let name = |func: u64| format!("0x{:016x}", func);
+
+        // Excluding 0x20 moves 0x40 up to 0x10
+        let filter = SymbolFilter::new(&[], &[name(0x20)]).unwrap();
+        let filtered = tree.filter(&filter);
+        let top = filtered.node_at(&[0]).unwrap();
+        let funcs: Vec<_> = top.children().map(|node| node.func()).collect();
+        assert_eq!(funcs, [0x40, 0x30]);
+        assert_eq!(top.self_time(), Some(Duration::from_nanos(690)));
+        assert_eq!(tree.filtered_path(&filter, &[0, 0, 0]), [0, 0]);
+        assert_eq!(tree.filtered_path(&filter, &[0, 1, 0]), [0, 1, 0]);
+        assert_eq!(tree.filtered_path(&filter, &[0, 0]), [0]);
+        assert_eq!(tree.unfiltered_path(&filter, &[0, 0]), Some(vec![0, 0, 0]));
+        assert_eq!(
+            tree.unfiltered_path(&filter, &[0, 1, 0]),
+            Some(vec![0, 1, 0])
+        );
+        assert_eq!(tree.unfiltered_path(&filter, &[0, 2]), None);
+
+        // Including 0x50 keeps only the calls leading to it
+        let filter = SymbolFilter::new(&[name(0x50)], &[]).unwrap();
+        let filtered = tree.filter(&filter);
+        let top = filtered.node_at(&[0]).unwrap();
+        let funcs: Vec<_> = top.children().map(|node| node.func()).collect();
+        assert_eq!(funcs, [0x30]);
+        assert_eq!(tree.filtered_path(&filter, &[0, 1, 0]), [0, 0, 0]);
+        assert_eq!(tree.filtered_path(&filter, &[0, 0, 0]), [0]);
+        assert_eq!(
+            tree.unfiltered_path(&filter, &[0, 0, 0]),
+            Some(vec![0, 1, 0])
+        );
+    }
+
+    #[test]
+    fn test_filter_offsets() {
+        let mut memcpy = node(0x24, 100, vec![node(0x40, 10, vec![])]);
+        memcpy.set_symbol(Arc::new(Symbol {
+            name: "memcpy".to_string(),
+            addr: 0x20,
+            size: 0x10,
+            offset: 4,
+            location: None,
+        }));
+        assert_eq!(memcpy.name(), "memcpy+0x4");
+        let tree = FtraceTree::new(
+            String::new(),
+            vec![node(0x10, 1000, vec![memcpy, node(0x30, 300, vec![])])],
+        );
+
+        // Anchored patterns match the function whatever the offset
+        let filter = SymbolFilter::new(&[], &["^memcpy$".to_string()]).unwrap();
+        let filtered = tree.filter(&filter);
+        let top = filtered.node_at(&[0]).unwrap();
+        let funcs: Vec<_> = top.children().map(|node| node.func()).collect();
+        assert_eq!(funcs, [0x40, 0x30]);
+        assert_eq!(tree.filtered_path(&filter, &[0, 0, 0]), [0, 0]);
+        assert_eq!(tree.unfiltered_path(&filter, &[0, 0]), Some(vec![0, 0, 0]));
+
+        let filter = SymbolFilter::new(&["^memcpy$".to_string()], &[]).unwrap();
+        let filtered = tree.filter(&filter);
+        let top = filtered.node_at(&[0]).unwrap();
+        let funcs: Vec<_> = top.children().map(|node| node.func()).collect();
+        assert_eq!(funcs, [0x24]);
+        assert_eq!(tree.filtered_path(&filter, &[0, 0, 0]), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_aggregate_offsets() {
+        let symbol = |offset| Symbol {
+            name: "leaf".to_string(),
+            addr: 0x20,
+            size: 0x10,
+            offset,
+            location: None,
+        };
+        let mut exact = node(0x20, 100, vec![]);
+        exact.set_symbol(Arc::new(symbol(0)));
+        let mut inner = node(0x28, 200, vec![]);
+        inner.set_symbol(Arc::new(symbol(8)));
+        assert_eq!(inner.name(), "leaf+0x8");
+        assert_eq!(inner.function(), "leaf");
+
+        let tree = FtraceTree::new(String::new(), vec![node(0x10, 1000, vec![inner, exact])]);
+        let aggregated = tree.aggregate();
+        let callees: Vec<_> = aggregated.node_at(&[0]).unwrap().children().collect();
+        assert_eq!(callees.len(), 1);
+        assert_eq!(callees[0].name(), "leaf");
+        assert_eq!(callees[0].calls(), 2);
+    }
+}
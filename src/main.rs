@@ -1,12 +1,22 @@
-use crate::{app::App, cli::Cli, ui::term::TerminalContext};
+use crate::{
+    app::App,
+    cli::{Cli, Command},
+    config::Config,
+    ui::term::TerminalContext,
+};
 use clap::Parser as _;
 use color_eyre::eyre::Result;
 use std::ops::DerefMut;
 
 mod app;
+mod bookmarks;
 mod cli;
+mod config;
+mod diff;
 mod ftrace;
 mod reader;
+mod runs;
+mod session;
 mod symbol;
 mod ui;
 mod utils;
@@ -15,14 +25,30 @@ mod utils;
 async fn main() -> Result<()> {
     color_eyre::install()?;
     let args = Cli::parse();
-    tui_main(args).await
+    let config = Config::load(&args)?;
+    match &args.command {
+        Some(Command::Diff(diff_args)) if !diff_args.tui => {
+            diff::diff_main(diff_args, &config).await
+        }
+        Some(Command::Runs(runs_args)) => runs::runs_main(runs_args, &config).await,
+        _ => tui_main(args, config).await,
+    }
 }
 
 #[allow(clippy::await_holding_lock)]
-async fn tui_main(args: Cli) -> Result<()> {
+async fn tui_main(args: Cli, config: Config) -> Result<()> {
     let terminal_ctx = TerminalContext::get()?;
     let mut terminal_lock = terminal_ctx.terminal().lock().unwrap();
 
-    let mut app = App::new(args);
-    app.run(terminal_lock.deref_mut()).await
+    let mut app = App::new(args, config);
+    let result = app.run(terminal_lock.deref_mut()).await;
+    // Restore the terminal first, so that the error stays on screen
+    drop(terminal_lock);
+    drop(terminal_ctx);
+    if result.is_ok()
+        && let Err(e) = app.save_session()
+    {
+        eprintln!("{e}");
+    }
+    result
 }
@@ -0,0 +1,119 @@
+use crate::{
+    app::load_trace,
+    cli::RunsArgs,
+    config::Config,
+    diff::format_change,
+    ftrace::{Change, FtraceTree, RunComparison, RunStats, compare_runs, run_stats},
+    utils::{format_duration, format_duration_diff},
+};
+use color_eyre::eyre::{Result, eyre};
+use futures::future::try_join_all;
+use std::{
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+};
+
+/// Changes with a lower p-value are marked as significant
+const SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+/// Print the spread of every function across runs, or its change between two
+/// groups of runs
+pub async fn runs_main(args: &RunsArgs, config: &Config) -> Result<()> {
+    let new = load_runs(&args.runs, config).await?;
+    let mut out = io::stdout().lock();
+    match &args.base {
+        Some(base) => {
+            let base = load_runs(base, config).await?;
+            writeln!(out, "{} base runs, {} new runs", base.len(), new.len())?;
+            writeln!(
+                out,
+                "{:>12} {:>8}  {:>10} {:>10}  {:>10} {:>10}  {:>8}  Function",
+                "Δ median", "%", "base", "new", "base σ", "new σ", "p",
+            )?;
+            for comparison in compare_runs(&base, &new) {
+                writeln!(out, "{}", format_comparison(&comparison))?;
+            }
+        }
+        None => {
+            writeln!(out, "{} runs", new.len())?;
+            writeln!(
+                out,
+                "{:>10} {:>10} {:>10} {:>10}  {:>10} {:>7} {:>7}  Function",
+                "median", "σ", "min", "max", "self", "calls", "runs",
+            )?;
+            for stats in run_stats(&new) {
+                writeln!(out, "{}", format_stats(&stats, new.len()))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Load every trace matched by `pattern`
+async fn load_runs(pattern: &str, config: &Config) -> Result<Vec<FtraceTree>> {
+    let paths = expand_runs(pattern)?;
+    let traces = try_join_all(
+        paths
+            .iter()
+            .map(|path| load_trace(path, config.timebase, config.elf_sources_with(&[]))),
+    )
+    .await?;
+    Ok(traces.into_iter().map(|trace| trace.tree).collect())
+}
+
+/// Files in the directory `pattern`, or matched by the glob `pattern`
+fn expand_runs(pattern: &str) -> Result<Vec<PathBuf>> {
+    let mut paths = if Path::new(pattern).is_dir() {
+        std::fs::read_dir(pattern)?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<io::Result<Vec<_>>>()?
+    } else {
+        glob::glob(pattern)?.collect::<Result<Vec<_>, _>>()?
+    };
+    paths.retain(|path| path.is_file());
+    paths.sort();
+    if paths.is_empty() {
+        return Err(eyre!("No trace found in {}", pattern));
+    }
+    Ok(paths)
+}
+
+fn format_stats(stats: &RunStats, runs: usize) -> String {
+    format!(
+        "{} {} {} {}  {} {:>7} {:>7}  {}",
+        format_duration(stats.total_time.median),
+        format_duration(stats.total_time.stddev),
+        format_duration(stats.total_time.min),
+        format_duration(stats.total_time.max),
+        format_duration(stats.self_time.median),
+        stats.calls,
+        format!("{}/{}", stats.runs, runs),
+        stats.name,
+    )
+}
+
+fn format_comparison(comparison: &RunComparison) -> String {
+    let (base, new) = (&comparison.base.total_time, &comparison.new.total_time);
+    let change = Change::between(
+        base.median,
+        new.median,
+        comparison.base.runs > 0,
+        comparison.new.runs > 0,
+    );
+    let p_value = match comparison.p_value {
+        Some(p) if p < SIGNIFICANCE_LEVEL => format!("{:.3}*", p),
+        Some(p) => format!("{:.3} ", p),
+        None => "n/a ".to_string(),
+    };
+    format!(
+        "{:>12} {:>8}  {} {}  {} {}  {:>8}  {}",
+        format_duration_diff(comparison.median_diff()),
+        format_change(change),
+        format_duration(base.median),
+        format_duration(new.median),
+        format_duration(base.stddev),
+        format_duration(new.stddev),
+        p_value,
+        comparison.name,
+    )
+}
@@ -0,0 +1,142 @@
+use crate::ui::components::TreeSession;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+const APP_STATE_DIR: &str = "optee_ftrace_viewer";
+const SESSIONS_DIR: &str = "sessions";
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("Failed to hash trace {}: {source}", path.display())]
+    Hash {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Failed to read session {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Failed to parse session {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("Failed to write session {}: {source}", path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Failed to serialize session: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+/// State of the UI restored on the next run of the same trace
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SessionState {
+    pub tree: TreeSession,
+}
+
+/// File the UI state of a trace is saved to, named after the hash of the
+/// trace content so that it follows the trace when it is moved or renamed
+#[derive(Debug)]
+pub struct Session {
+    path: PathBuf,
+}
+
+impl Session {
+    /// The session of the trace at `trace`, none if there is no directory to
+    /// save sessions to
+    pub fn open(trace: &Path) -> Result<Option<Self>, SessionError> {
+        let Some(dir) = dirs::state_dir().or_else(dirs::cache_dir) else {
+            return Ok(None);
+        };
+        let hash =
+            File::open(trace)
+                .and_then(content_hash)
+                .map_err(|source| SessionError::Hash {
+                    path: trace.to_path_buf(),
+                    source,
+                })?;
+        let path = dir
+            .join(APP_STATE_DIR)
+            .join(SESSIONS_DIR)
+            .join(format!("{:016x}.toml", hash));
+        Ok(Some(Self { path }))
+    }
+
+    /// The saved state, none if the trace was never opened
+    pub fn load(&self) -> Result<Option<SessionState>, SessionError> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => {
+                return Err(SessionError::Read {
+                    path: self.path.clone(),
+                    source,
+                });
+            }
+        };
+        toml::from_str(&content)
+            .map(Some)
+            .map_err(|source| SessionError::Parse {
+                path: self.path.clone(),
+                source,
+            })
+    }
+
+    pub fn save(&self, state: &SessionState) -> Result<(), SessionError> {
+        let content = toml::to_string(state)?;
+        let write_error = |source| SessionError::Write {
+            path: self.path.clone(),
+            source,
+        };
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(write_error)?;
+        }
+        fs::write(&self.path, content).map_err(write_error)
+    }
+}
+
+/// FNV-1a hash of everything read from `reader`, which unlike the hashers of
+/// the standard library stays the same across builds
+fn content_hash(mut reader: impl Read) -> io::Result<u64> {
+    let mut hash = FNV_OFFSET_BASIS;
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let len = match reader.read(&mut buf) {
+            Ok(0) => return Ok(hash),
+            Ok(len) => len,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for &byte in &buf[..len] {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(content_hash(&b""[..]).unwrap(), FNV_OFFSET_BASIS);
+        assert_eq!(content_hash(&b"a"[..]).unwrap(), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(content_hash(&b"foobar"[..]).unwrap(), 0x8594_4171_f739_67e8);
+    }
+}
@@ -1,18 +1,22 @@
 use super::{error::SymbolError, region::RegionFlags};
-use crate::symbol::region::{LoadInfo, TeeInfo};
+use crate::symbol::region::{LoadInfo, TaRegionInfo, TeeInfo};
 use regex::Regex;
 use std::{collections::HashMap, str::FromStr, sync::LazyLock};
 use uuid::Uuid;
 
+// The header lines are matched anywhere in a line, so that log prefixes
+// (e.g. "E/LD:  ") and the leading indentation of some releases are ignored.
+
 // Format in core/kernel/user_ta.c:user_ta_dump_ftrace()
 const TEE_LOAD_ADDR_RS: &str = r"TEE load address @ (?P<load_addr>0x[0-9a-f]+)";
 // Format in ldelf/ta_elf.c:print_seg()
-const REGION_RS: &str = r"region +[0-9]+: va (?P<va>0x[0-9a-f]+) pa (?P<pa>0x[0-9a-f]+) size (?P<size>0x[0-9a-f]+) flags (?P<flags>[rwxs-]{4}) (\[(?P<elf_idx>[0-9]+)\])?";
+const REGION_RS: &str = r"region +[0-9]+: va (?P<va>0x[0-9a-f]+) pa (?P<pa>0x[0-9a-f]+) size (?P<size>0x[0-9a-f]+) flags (?P<flags>[rwxs-]{3,4})(?: \[(?P<elf_idx>[0-9]+)\])?";
 // Format in ldelf/ta_elf.c:ta_elf_print_mappings()
 const ELF_LIST_RS: &str =
-    r"\[(?P<idx>[0-9]+)\] (?P<uuid>[0-9a-f\-]+) @ (?P<load_addr>0x[0-9a-f\-]+)";
+    r"\[(?P<idx>[0-9]+)\] (?P<uuid>[0-9a-f\-]+) @ (?:0x)?(?P<load_addr>[0-9a-f]+)";
 // Format in ldelf/ftrace.c:ftrace_init()
-const FUNC_GRAPH_RS: &str = r"Function graph for TA: (?P<uuid>[0-9a-f\-]+) @ (?P<addr>[0-9a-f]+)";
+const FUNC_GRAPH_RS: &str =
+    r"Function graph for TA: (?P<uuid>[0-9a-f\-]+) @ (?:0x)?(?P<addr>[0-9a-f]+)";
 
 static TEE_LOAD_ADDR_REGEX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(TEE_LOAD_ADDR_RS).expect("Failed to compile load addr regex"));
@@ -23,15 +27,20 @@ static ELF_LIST_REGEX: LazyLock<Regex> =
 static FUNC_GRAPH_REGEX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(FUNC_GRAPH_RS).expect("Failed to compile function graph regex"));
 
+/// Load information parsed from the text header of an ftrace dump.
+///
+/// Which lines are present and in which order differs between OP-TEE
+/// releases, so every part is optional. Without a region table, addresses
+/// are looked up in the whole image of the TA from the function graph line.
 #[allow(unused)]
 #[derive(Clone, Debug)]
 pub struct SymbolInfo {
-    pub tee_load_addr: u64,
+    pub tee_load_addr: Option<u64>,
     pub regions: Vec<RegionData>,
     pub elf_list: HashMap<usize, ElfInfo>,
     pub title: String,
-    pub ta_uuid: Uuid,
-    pub ta_load_addr: u64,
+    pub ta_uuid: Option<Uuid>,
+    pub ta_load_addr: Option<u64>,
 }
 
 impl SymbolInfo {
@@ -49,12 +58,27 @@ impl SymbolInfo {
                 ));
             }
         }
-        if addr >= self.tee_load_addr {
-            Some(LoadInfo::Tee(TeeInfo {
-                load_addr: self.tee_load_addr,
-            }))
-        } else {
-            None
+
+        let tee = self
+            .tee_load_addr
+            .filter(|&load_addr| addr >= load_addr)
+            .map(|load_addr| LoadInfo::Tee(TeeInfo { load_addr }));
+        // Without a region table, fall back to the TA load address from the
+        // function graph line and pick whichever image starts closest below
+        // the address.
+        let ta = self
+            .regions
+            .is_empty()
+            .then_some(self.ta_uuid.zip(self.ta_load_addr))
+            .flatten()
+            .filter(|&(_, load_addr)| addr >= load_addr)
+            .map(|(uuid, load_addr)| {
+                LoadInfo::TrustedApp(TaRegionInfo::whole_image(uuid, load_addr))
+            });
+        match (tee, ta) {
+            (Some(tee), Some(ta)) if ta.load_addr() > tee.load_addr() => Some(ta),
+            (Some(tee), _) => Some(tee),
+            (None, ta) => ta,
         }
     }
 }
@@ -63,55 +87,61 @@ impl FromStr for SymbolInfo {
     type Err = SymbolError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines().peekable();
-        // Parsing OP-TEE load address
-        let tee_load_addr_line = lines.next().ok_or(SymbolError::InvalidRegionTable)?;
-        let tee_load_addr_caps = TEE_LOAD_ADDR_REGEX
-            .captures(tee_load_addr_line)
-            .ok_or(SymbolError::InvalidSymbolInfo)?;
-        let tee_load_addr_hex = tee_load_addr_caps
-            .name("load_addr")
-            .and_then(|m| m.as_str().strip_prefix("0x"))
-            .ok_or(SymbolError::InvalidSymbolInfo)?;
-        let tee_load_addr = u64::from_str_radix(tee_load_addr_hex, 16)
-            .map_err(|_| SymbolError::InvalidSymbolInfo)?;
-
-        // Parsing region table
+        let mut tee_load_addr = None;
         let mut regions = Vec::new();
-        while let Some(region_line) = lines.next_if(|line| line.starts_with("region")) {
-            let region_data = region_line.parse::<RegionData>()?;
-            regions.push(region_data);
-        }
-
-        // Parsing ELF list
         let mut elf_list = HashMap::new();
-        while let Some(elf_list_line) = lines.next_if(|line| line.trim_start().starts_with('[')) {
-            let elf_info = elf_list_line.parse::<ElfInfo>()?;
-            elf_list.insert(elf_info.idx, elf_info);
+        let mut func_graph = None;
+        let mut found = false;
+
+        for line in s.lines() {
+            if let Some(caps) = TEE_LOAD_ADDR_REGEX.captures(line) {
+                let tee_load_addr_hex = caps
+                    .name("load_addr")
+                    .and_then(|m| m.as_str().strip_prefix("0x"))
+                    .ok_or(SymbolError::InvalidSymbolInfo)?;
+                tee_load_addr = Some(
+                    u64::from_str_radix(tee_load_addr_hex, 16)
+                        .map_err(|_| SymbolError::InvalidSymbolInfo)?,
+                );
+            } else if REGION_REGEX.is_match(line) {
+                regions.push(line.parse::<RegionData>()?);
+            } else if ELF_LIST_REGEX.is_match(line) {
+                let elf_info = line.parse::<ElfInfo>()?;
+                elf_list.insert(elf_info.idx, elf_info);
+            } else if let Some(caps) = FUNC_GRAPH_REGEX.captures(line) {
+                let uuid_str = caps
+                    .name("uuid")
+                    .ok_or(SymbolError::InvalidSymbolInfo)?
+                    .as_str();
+                let ta_addr_hex = caps
+                    .name("addr")
+                    .ok_or(SymbolError::InvalidSymbolInfo)?
+                    .as_str();
+                let ta_uuid = Uuid::parse_str(uuid_str)?;
+                let ta_load_addr = u64::from_str_radix(ta_addr_hex, 16)
+                    .map_err(|_| SymbolError::InvalidSymbolInfo)?;
+                // Strip any log prefix from the title
+                let title = line[caps.get(0).map_or(0, |m| m.start())..].trim_end();
+                func_graph = Some((title.to_string(), ta_uuid, ta_load_addr));
+            } else {
+                continue;
+            }
+            found = true;
         }
 
-        // Parsing function graph info
-        let func_graph_line = lines.next().ok_or(SymbolError::InvalidRegionTable)?;
-        let func_graph_caps = FUNC_GRAPH_REGEX
-            .captures(func_graph_line)
-            .ok_or(SymbolError::InvalidSymbolInfo)?;
-        let uuid_str = func_graph_caps
-            .name("uuid")
-            .ok_or(SymbolError::InvalidSymbolInfo)?
-            .as_str();
-        let ta_addr_hex = func_graph_caps
-            .name("addr")
-            .ok_or(SymbolError::InvalidSymbolInfo)?
-            .as_str();
-        let ta_uuid = Uuid::parse_str(uuid_str)?;
-        let ta_load_addr =
-            u64::from_str_radix(ta_addr_hex, 16).map_err(|_| SymbolError::InvalidSymbolInfo)?;
+        if !found {
+            return Err(SymbolError::InvalidSymbolInfo);
+        }
 
+        let (title, ta_uuid, ta_load_addr) = match func_graph {
+            Some((title, uuid, load_addr)) => (title, Some(uuid), Some(load_addr)),
+            None => (String::new(), None, None),
+        };
         Ok(Self {
             tee_load_addr,
             regions,
             elf_list,
-            title: func_graph_line.to_string(),
+            title,
             ta_uuid,
             ta_load_addr,
         })
@@ -143,8 +173,8 @@ impl FromStr for ElfInfo {
             .as_str();
         let load_addr_str = caps
             .name("load_addr")
-            .and_then(|m| m.as_str().strip_prefix("0x"))
-            .ok_or(SymbolError::InvalidSymbolInfo)?;
+            .ok_or(SymbolError::InvalidSymbolInfo)?
+            .as_str();
 
         let idx = idx_str
             .parse()
@@ -219,3 +249,93 @@ impl FromStr for RegionData {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Synthetic header of `tests/fixtures/headers`, see the README there
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/headers/",
+                $name
+            ))
+        };
+    }
+
+    const TA_UUID: &str = "8aaaf200-2450-11e4-abe2-0002a5d5c51b";
+
+    #[test]
+    fn test_parse_header_without_regions() {
+        let info: SymbolInfo = fixture!("no-region-table.txt").parse().unwrap();
+        assert_eq!(info.tee_load_addr, Some(0x0e10_0000));
+        assert!(info.regions.is_empty());
+        assert!(info.elf_list.is_empty());
+        assert_eq!(info.ta_uuid, Some(Uuid::parse_str(TA_UUID).unwrap()));
+        assert_eq!(info.ta_load_addr, Some(0x4002_0000));
+
+        let load_info = info.find_by_addr(0x4002_1234).unwrap();
+        assert!(!load_info.is_tee());
+        assert_eq!(load_info.calculate_reladdr(0x4002_1234), Some(0x1234));
+        assert!(info.find_by_addr(0x0e10_1000).unwrap().is_tee());
+    }
+
+    #[test]
+    fn test_parse_header_with_regions() {
+        let info: SymbolInfo = fixture!("region-table.txt").parse().unwrap();
+        assert_eq!(info.tee_load_addr, Some(0x0e10_0000));
+        assert_eq!(info.regions.len(), 7);
+        assert_eq!(info.regions[0].elf_idx, None);
+        assert_eq!(info.regions[5].elf_idx, Some(0));
+        assert_eq!(
+            info.regions[5].flags,
+            RegionFlags::READ | RegionFlags::EXEC | RegionFlags::SECURE
+        );
+        assert_eq!(info.elf_list.len(), 1);
+        assert_eq!(info.elf_list[&0].load_addr, 0x4002_0000);
+        assert_eq!(
+            info.title,
+            format!("Function graph for TA: {} @ 40020000", TA_UUID)
+        );
+
+        let load_info = info.find_by_addr(0x4002_0010).unwrap();
+        assert_eq!(load_info.filename(), format!("{}.elf", TA_UUID));
+    }
+
+    #[test]
+    fn test_parse_reordered_header() {
+        // The function graph line comes first and a shared library is loaded
+        let info: SymbolInfo = fixture!("reordered-shared-library.txt").parse().unwrap();
+        assert_eq!(info.tee_load_addr, Some(0x0e10_0000));
+        assert_eq!(info.regions.len(), 9);
+        assert_eq!(info.elf_list.len(), 2);
+        assert_eq!(info.ta_load_addr, Some(0x4002_3000));
+
+        let load_info = info.find_by_addr(0x4004_c100).unwrap();
+        assert_eq!(
+            load_info.filename(),
+            "ffd2bded-ab7d-4988-95ee-e4962fff7154.elf"
+        );
+        assert_eq!(load_info.calculate_reladdr(0x4004_c100), Some(0x100));
+    }
+
+    #[test]
+    fn test_parse_header_with_log_prefixes() {
+        let info: SymbolInfo = fixture!("log-prefixes.txt").parse().unwrap();
+        assert_eq!(info.tee_load_addr, Some(0x0e10_0000));
+        assert_eq!(info.regions.len(), 4);
+        assert_eq!(info.elf_list.len(), 1);
+        assert_eq!(
+            info.title,
+            format!("Function graph for TA: {} @ 40023000", TA_UUID)
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_header() {
+        assert!("".parse::<SymbolInfo>().is_err());
+        assert!("garbage\nmore garbage\n".parse::<SymbolInfo>().is_err());
+    }
+}
@@ -2,7 +2,7 @@ use crate::symbol::info::{ElfInfo, RegionData};
 
 use super::error::SymbolError;
 use bitflags::bitflags;
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 use uuid::Uuid;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -51,6 +51,22 @@ pub struct TaRegionInfo {
     pub flags: RegionFlags,
 }
 
+impl TaRegionInfo {
+    /// Region info covering a whole TA image, for headers without a region
+    /// table
+    pub fn whole_image(uuid: Uuid, load_addr: u64) -> Self {
+        Self {
+            elf_idx: 0,
+            uuid,
+            load_addr,
+            va: load_addr,
+            pa: 0,
+            size: 0,
+            flags: RegionFlags::empty(),
+        }
+    }
+}
+
 impl From<(RegionData, ElfInfo)> for TaRegionInfo {
     fn from((region, elf): (RegionData, ElfInfo)) -> Self {
         Self {
@@ -94,3 +110,17 @@ impl FromStr for RegionFlags {
         Ok(flags)
     }
 }
+
+impl fmt::Display for RegionFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (flag, c) in [
+            (RegionFlags::READ, 'r'),
+            (RegionFlags::WRITE, 'w'),
+            (RegionFlags::EXEC, 'x'),
+            (RegionFlags::SECURE, 's'),
+        ] {
+            write!(f, "{}", if self.contains(flag) { c } else { '-' })?;
+        }
+        Ok(())
+    }
+}
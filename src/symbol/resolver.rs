@@ -2,20 +2,51 @@ use crate::{symbol::region::LoadInfo, utils::FormatFn};
 use addr2line::Loader;
 use color_eyre::eyre::{Result, eyre};
 use moka::{future::Cache, ops::compute::Op};
+use object::{Object as _, ObjectSymbol as _};
 use std::{
     collections::HashMap,
     ffi::OsStr,
-    fmt::{Debug, Formatter},
+    fmt::{self, Debug, Display, Formatter},
     future::ready,
     path::{Path, PathBuf},
     sync::Arc,
 };
 use uuid::Uuid;
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Symbol {
+    pub name: String,
+    /// Start address of the symbol in the ELF file
+    pub addr: u64,
+    /// Size of the symbol in bytes, `0` if the ELF file does not record it
+    pub size: u64,
+    /// Offset of the resolved address from the start of the symbol
+    pub offset: u64,
+    /// Source file and line of the resolved address, if the ELF file has
+    /// debug information
+    pub location: Option<String>,
+}
+
+impl Symbol {
+    pub fn is_exact(&self) -> bool {
+        self.offset == 0
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_exact() {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{}+0x{:x}", self.name, self.offset)
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct CachedSymbolResolver {
     resolver: SymbolResolver,
-    cache: Cache<u64, Arc<String>>,
+    cache: Cache<u64, Arc<Symbol>>,
 }
 
 impl CachedSymbolResolver {
@@ -30,7 +61,15 @@ impl CachedSymbolResolver {
         }
     }
 
-    pub async fn resolve_symbol(&mut self, load_info: &LoadInfo, addr: u64) -> Option<Arc<String>> {
+    pub fn add_source(&mut self, source: PathBuf) {
+        self.resolver.add_source(source);
+    }
+
+    pub fn elf_path(&mut self, load_info: &LoadInfo) -> Option<&Path> {
+        self.resolver.elf_path(load_info)
+    }
+
+    pub async fn resolve_symbol(&mut self, load_info: &LoadInfo, addr: u64) -> Option<Arc<Symbol>> {
         self.cache
             .entry(addr)
             .and_compute_with(
@@ -45,8 +84,33 @@ impl CachedSymbolResolver {
     }
 }
 
+struct ElfFile {
+    path: PathBuf,
+    loader: Loader,
+    /// Symbol sizes keyed by symbol address, which `Loader` does not expose
+    symbol_sizes: HashMap<u64, u64>,
+}
+
+impl ElfFile {
+    fn open(path: &Path) -> Result<Self> {
+        let loader = Loader::new(path).map_err(|e| eyre!("{}", e))?;
+        let data = std::fs::read(path)?;
+        let object = object::File::parse(data.as_slice())?;
+        let symbol_sizes = object
+            .symbols()
+            .filter(|sym| sym.size() > 0)
+            .map(|sym| (sym.address(), sym.size()))
+            .collect();
+        Ok(Self {
+            path: path.to_path_buf(),
+            loader,
+            symbol_sizes,
+        })
+    }
+}
+
 pub struct SymbolResolver {
-    elf: HashMap<Option<Uuid>, Loader>,
+    elf: HashMap<Option<Uuid>, ElfFile>,
     sources: Vec<PathBuf>,
 }
 
@@ -58,27 +122,61 @@ impl SymbolResolver {
         }
     }
 
-    pub fn resolve_symbol(&mut self, load_info: &LoadInfo, mut addr: u64) -> Option<String> {
-        let loader = self.load_elf(load_info).ok()?;
+    /// Add a file or directory to search for ELF files.
+    ///
+    /// Only ELF files that have not been found yet are looked up in the new
+    /// source.
+    pub fn add_source(&mut self, source: PathBuf) {
+        self.sources.push(source);
+    }
+
+    /// Path of the ELF file found for `load_info`, if any
+    pub fn elf_path(&mut self, load_info: &LoadInfo) -> Option<&Path> {
+        self.load_elf(load_info).ok().map(|elf| elf.path.as_path())
+    }
+
+    pub fn resolve_symbol(&mut self, load_info: &LoadInfo, mut addr: u64) -> Option<Symbol> {
+        let elf = self.load_elf(load_info).ok()?;
         if load_info.is_tee()
-            && let Some(range) = loader.get_section_range(b".text")
+            && let Some(range) = elf.loader.get_section_range(b".text")
         {
             addr += range.begin;
         }
 
-        loader.find_symbol(addr).map(|s| s.to_owned())
+        let symbol = elf.loader.find_symbol_info(addr)?;
+        let location = elf
+            .loader
+            .find_location(addr)
+            .ok()
+            .flatten()
+            .and_then(|location| match (location.file, location.line) {
+                (Some(file), Some(line)) => Some(format!("{}:{}", file, line)),
+                (file, _) => file.map(str::to_string),
+            });
+        Some(Symbol {
+            name: symbol.name().to_owned(),
+            addr: symbol.address(),
+            size: elf
+                .symbol_sizes
+                .get(&symbol.address())
+                .copied()
+                .unwrap_or_default(),
+            offset: addr - symbol.address(),
+            location,
+        })
     }
 
-    pub fn load_elf(&mut self, load_info: &LoadInfo) -> Result<&Loader> {
+    fn load_elf(&mut self, load_info: &LoadInfo) -> Result<&ElfFile> {
         let key = match load_info {
             LoadInfo::TrustedApp(info) => Some(info.uuid),
             LoadInfo::Tee(_) => None,
         };
         if !self.elf.contains_key(&key) {
+            // Sources are searched in order, the first match wins
             for source in &self.sources {
                 if let Some(elf_path) = Self::find_elf_in_source(source, load_info) {
-                    let loader = Loader::new(elf_path).map_err(|e| eyre!("{}", e))?;
-                    self.elf.insert(key, loader);
+                    self.elf.insert(key, ElfFile::open(&elf_path)?);
+                    break;
                 }
             }
         }
@@ -115,7 +213,7 @@ impl Debug for SymbolResolver {
                     // Loader does not implement Debug, so we just print the keys
                     // and a placeholder for the values.
                     f.debug_map()
-                        .entries(self.elf.keys().map(|k| (k, "Loader { ... }")))
+                        .entries(self.elf.keys().map(|k| (k, "ElfFile { ... }")))
                         .finish()
                 }),
             )
@@ -0,0 +1,116 @@
+use super::trace_tree::PATH_SEPARATOR;
+use crate::{bookmarks::Bookmark, config::Config, ui::keymap::Action};
+use crossterm::event::{KeyEvent, KeyEventKind};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, List, ListState, Paragraph},
+};
+use std::sync::Arc;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BookmarkListResult {
+    Pending,
+    /// Jump to the bookmark at this index
+    Jump(usize),
+    /// Remove the bookmark at this index
+    Remove(usize),
+    Close,
+}
+
+/// Popup listing the bookmarks of the trace
+#[derive(Debug)]
+pub struct BookmarkList {
+    config: Arc<Config>,
+    /// Bookmarks and whether their call is found in the trace
+    entries: Vec<(Bookmark, bool)>,
+    state: ListState,
+}
+
+impl BookmarkList {
+    pub fn new(config: Arc<Config>, entries: Vec<(Bookmark, bool)>) -> Self {
+        let state = ListState::default().with_selected((!entries.is_empty()).then_some(0));
+        Self {
+            config,
+            entries,
+            state,
+        }
+    }
+
+    /// Replace the bookmarks listed, keeping the selection in place
+    pub fn update(&mut self, entries: Vec<(Bookmark, bool)>) {
+        let selected = self
+            .state
+            .selected()
+            .map(|idx| idx.min(entries.len().saturating_sub(1)));
+        self.state.select(selected.filter(|_| !entries.is_empty()));
+        self.entries = entries;
+    }
+
+    pub fn handle_key(&mut self, key_event: KeyEvent) -> BookmarkListResult {
+        if key_event.kind == KeyEventKind::Release {
+            return BookmarkListResult::Pending;
+        }
+        match self.config.keymap.action(&key_event) {
+            Some(Action::Up) => self.state.select_previous(),
+            Some(Action::Down) => self.state.select_next(),
+            Some(Action::First) => self.state.select_first(),
+            Some(Action::Last) => self.state.select_last(),
+            Some(Action::Toggle) => {
+                if let Some(idx) = self.state.selected() {
+                    return BookmarkListResult::Jump(idx);
+                }
+            }
+            Some(Action::Delete) => {
+                if let Some(idx) = self.state.selected() {
+                    return BookmarkListResult::Remove(idx);
+                }
+            }
+            Some(Action::Quit | Action::Bookmarks) => return BookmarkListResult::Close,
+            _ => (),
+        }
+        BookmarkListResult::Pending
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let theme = &self.config.theme;
+        let height = (self.entries.len().max(1) as u16).saturating_add(2);
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Percentage(70)])
+            .flex(Flex::Center)
+            .areas(area);
+        let block = Block::bordered()
+            .title(" Bookmarks ")
+            .border_type(BorderType::Rounded);
+        frame.render_widget(Clear, area);
+        if self.entries.is_empty() {
+            frame.render_widget(Paragraph::new("No bookmarks").block(block), area);
+            return;
+        }
+
+        let items = self.entries.iter().map(|(bookmark, found)| {
+            let mut spans = vec![Span::styled(
+                format!("{}()", bookmark.name()),
+                theme.name_style(),
+            )];
+            if !bookmark.note.is_empty() {
+                spans.push(Span::raw(format!("  {}", bookmark.note)));
+            }
+            if !found {
+                spans.push(Span::styled("  (not found)", theme.regression_style()));
+            }
+            spans.push(Span::styled(
+                format!("  {}", bookmark.call_path.join(PATH_SEPARATOR)),
+                theme.self_time_style(),
+            ));
+            Line::from(spans)
+        });
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(theme.highlight_style());
+        frame.render_stateful_widget(list, area, &mut self.state);
+    }
+}
@@ -0,0 +1,263 @@
+use super::Component;
+use crate::{
+    app::AppMsg,
+    config::Config,
+    ftrace::{Butterfly, CallEdge, FtraceTree, ROOT_CALLER},
+    ui::{event::Event, keymap::Action},
+    utils::format_duration,
+};
+use crossterm::event::KeyEventKind;
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Cell, Paragraph, Row, Table, TableState},
+};
+use std::{sync::Arc, time::Duration};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+    Callers,
+    Callees,
+}
+
+/// Callers and callees of one function, side by side
+#[derive(Debug)]
+pub struct ButterflyComponent {
+    config: Arc<Config>,
+    tree: Option<Arc<FtraceTree>>,
+    butterfly: Option<Butterfly>,
+    /// Previously shown functions, to go back to after following an edge
+    history: Vec<String>,
+    focus: Side,
+    callers_state: TableState,
+    callees_state: TableState,
+}
+
+impl ButterflyComponent {
+    pub fn new(config: Arc<Config>) -> Self {
+        Self {
+            config,
+            tree: None,
+            butterfly: None,
+            history: Vec::new(),
+            focus: Side::Callees,
+            callers_state: TableState::default(),
+            callees_state: TableState::default(),
+        }
+    }
+
+    /// Show the callers and callees of the function `name`
+    pub fn show(&mut self, name: &str) {
+        if let Some(butterfly) = self.butterfly.take() {
+            self.history.push(butterfly.name);
+        }
+        self.load(name);
+    }
+
+    fn load(&mut self, name: &str) {
+        let Some(tree) = &self.tree else {
+            return;
+        };
+        let butterfly = Butterfly::new(tree, name);
+        self.callers_state
+            .select((!butterfly.callers.is_empty()).then_some(0));
+        self.callees_state
+            .select((!butterfly.callees.is_empty()).then_some(0));
+        self.butterfly = Some(butterfly);
+    }
+
+    fn back(&mut self) {
+        if let Some(name) = self.history.pop() {
+            self.load(&name);
+        }
+    }
+
+    fn focused_state(&mut self) -> &mut TableState {
+        match self.focus {
+            Side::Callers => &mut self.callers_state,
+            Side::Callees => &mut self.callees_state,
+        }
+    }
+
+    /// Follow the selected edge to the function at its other end
+    fn follow_selected(&mut self) {
+        let Some(butterfly) = &self.butterfly else {
+            return;
+        };
+        let (edges, state) = match self.focus {
+            Side::Callers => (&butterfly.callers, &self.callers_state),
+            Side::Callees => (&butterfly.callees, &self.callees_state),
+        };
+        let Some(edge) = state.selected().and_then(|i| edges.get(i)) else {
+            return;
+        };
+        if edge.name != ROOT_CALLER {
+            let name = edge.name.clone();
+            self.show(&name);
+        }
+    }
+
+    fn summary_line(&self, butterfly: &Butterfly) -> Line<'static> {
+        let theme = &self.config.theme;
+        Line::from_iter([
+            Span::styled(format!("{}()", butterfly.name), theme.name_style()),
+            Span::raw(format!("  {} calls  ", butterfly.calls)),
+            Span::styled(
+                format_duration(butterfly.total_time)
+                    .trim_start()
+                    .to_string(),
+                theme.time_style(),
+            ),
+            Span::raw(" "),
+            Span::styled(
+                format!(
+                    "(self: {})",
+                    format_duration(butterfly.self_time).trim_start()
+                ),
+                theme.self_time_style(),
+            ),
+        ])
+    }
+
+    fn edge_table(
+        &self,
+        title: &str,
+        edges: &[CallEdge],
+        upper_time: Duration,
+        focused: bool,
+    ) -> Table<'static> {
+        let theme = &self.config.theme;
+        let rows = edges.iter().map(|edge| {
+            let ratio = if upper_time.is_zero() {
+                Cell::from(Line::from("n/a").right_aligned()).style(theme.self_time_style())
+            } else {
+                let ratio =
+                    edge.total_time.as_nanos() as f64 / upper_time.as_nanos() as f64 * 100.0;
+                Cell::from(Line::from(format!("{:.2}%", ratio)).right_aligned())
+                    .style(theme.ratio_style())
+            };
+            Row::new([
+                Cell::from(edge.name.clone()).style(theme.name_style()),
+                Cell::from(Line::from(edge.calls.to_string()).right_aligned()),
+                Cell::from(format_duration(edge.total_time)).style(theme.time_style()),
+                ratio,
+            ])
+        });
+        let mut block = Block::bordered()
+            .title(format!(" {} ({}) ", title, edges.len()))
+            .border_type(BorderType::Rounded);
+        if !focused {
+            block = block.border_style(Style::new().add_modifier(Modifier::DIM));
+        }
+        let mut table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(8),
+                Constraint::Length(10),
+                Constraint::Length(8),
+            ],
+        )
+        .header(Row::new(["Function", "Calls", "Time", "%"]).style(theme.header_style()))
+        .block(block);
+        if focused {
+            table = table.row_highlight_style(theme.highlight_style());
+        }
+        table
+    }
+}
+
+impl Component<AppMsg> for ButterflyComponent {
+    fn handle(&mut self, event: Event<AppMsg>) {
+        match event {
+            Event::Key(key_event) if key_event.kind != KeyEventKind::Release => {
+                match self.config.keymap.action(&key_event) {
+                    Some(Action::Up) => {
+                        self.focused_state().select_previous();
+                    }
+                    Some(Action::Down) => {
+                        self.focused_state().select_next();
+                    }
+                    Some(Action::First) => {
+                        self.focused_state().select_first();
+                    }
+                    Some(Action::Last) => {
+                        self.focused_state().select_last();
+                    }
+                    Some(Action::PageUp) => {
+                        self.focused_state().scroll_up_by(10);
+                    }
+                    Some(Action::PageDown) => {
+                        self.focused_state().scroll_down_by(10);
+                    }
+                    Some(Action::Left) => {
+                        self.focus = Side::Callers;
+                    }
+                    Some(Action::Right) => {
+                        self.focus = Side::Callees;
+                    }
+                    Some(Action::Toggle) => {
+                        self.follow_selected();
+                    }
+                    Some(Action::Back) => {
+                        self.back();
+                    }
+                    _ => (),
+                }
+            }
+            Event::Message(AppMsg::UpdateTree(tree)) => {
+                self.tree = Some(tree);
+                // Symbols may have been resolved, refresh the figures
+                if let Some(butterfly) = self.butterfly.take() {
+                    self.load(&butterfly.name);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(" Callers and callees ")
+            .title_alignment(Alignment::Center)
+            .border_type(BorderType::Rounded);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let Some(butterfly) = &self.butterfly else {
+            let hint = format!(
+                "Select a function in the tree or the statistics and press {}",
+                self.config
+                    .keymap
+                    .keys(Action::Butterfly)
+                    .first()
+                    .map(|key| key.to_string())
+                    .unwrap_or_else(|| "<unbound>".to_string())
+            );
+            frame.render_widget(Paragraph::new(hint), inner);
+            return;
+        };
+        let [summary_area, tables_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(inner);
+        let [callers_area, callees_area] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(tables_area);
+
+        let callers = self.edge_table(
+            "Callers",
+            &butterfly.callers,
+            butterfly.total_time,
+            self.focus == Side::Callers,
+        );
+        let callees = self.edge_table(
+            "Callees",
+            &butterfly.callees,
+            butterfly.total_time,
+            self.focus == Side::Callees,
+        );
+        frame.render_widget(Paragraph::new(self.summary_line(butterfly)), summary_area);
+        frame.render_stateful_widget(callers, callers_area, &mut self.callers_state);
+        frame.render_stateful_widget(callees, callees_area, &mut self.callees_state);
+    }
+}
@@ -0,0 +1,414 @@
+use super::Component;
+use crate::{
+    app::AppMsg,
+    config::Config,
+    ftrace::{FtraceNode, FtraceTree},
+    ui::{event::Event, keymap::Action},
+    utils::format_duration,
+};
+use crossterm::event::{KeyEventKind, MouseButton, MouseEventKind};
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Layout, Position, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Paragraph},
+};
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
+    time::Duration,
+};
+
+/// A frame drawn on screen
+#[derive(Clone, Debug)]
+struct FlameFrame {
+    /// Child indices leading to the node from the top level
+    path: Vec<usize>,
+    area: Rect,
+}
+
+/// Calls drawn as frames as wide as their total time, with the callees of a
+/// call stacked below it
+#[derive(Debug)]
+pub struct FlameGraphComponent {
+    config: Arc<Config>,
+    tree: Option<Arc<FtraceTree>>,
+    /// Path of the frame spanning the whole width, empty for all top level calls
+    zoom: Vec<usize>,
+    selected: Vec<usize>,
+    hovered: Option<Vec<usize>>,
+    /// Frames of the last render, for navigation and mouse hits
+    frames: Vec<FlameFrame>,
+}
+
+impl FlameGraphComponent {
+    pub fn new(config: Arc<Config>) -> Self {
+        Self {
+            config,
+            tree: None,
+            zoom: Vec::new(),
+            selected: Vec::new(),
+            hovered: None,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Path of the selected frame, if any
+    pub fn selected_path(&self) -> Option<&[usize]> {
+        (!self.selected.is_empty()).then_some(self.selected.as_slice())
+    }
+
+    fn node(&self, path: &[usize]) -> Option<&FtraceNode> {
+        self.tree.as_ref()?.node_at(path)
+    }
+
+    fn zoom_to(&mut self, path: Vec<usize>) {
+        if path.is_empty() || self.node(&path).is_some() {
+            self.selected = path.clone();
+            self.zoom = path;
+        }
+    }
+
+    /// Zoom out to the caller of the frame spanning the whole width
+    fn zoom_out(&mut self) {
+        self.zoom.pop();
+    }
+
+    fn frame_at(&self, position: Position) -> Option<&FlameFrame> {
+        self.frames
+            .iter()
+            .find(|frame| frame.area.contains(position))
+    }
+
+    fn selected_frame(&self) -> Option<&FlameFrame> {
+        self.frames.iter().find(|frame| frame.path == self.selected)
+    }
+
+    /// Select the frame next to the selected one in the same row
+    fn select_sibling(&mut self, forward: bool) {
+        let Some(current) = self.selected_frame().map(|frame| frame.area) else {
+            return;
+        };
+        let row = self.frames.iter().filter(|frame| frame.area.y == current.y);
+        let next = if forward {
+            row.filter(|frame| frame.area.x > current.x)
+                .min_by_key(|frame| frame.area.x)
+        } else {
+            row.filter(|frame| frame.area.x < current.x)
+                .max_by_key(|frame| frame.area.x)
+        };
+        if let Some(frame) = next {
+            self.selected = frame.path.clone();
+        }
+    }
+
+    /// Select the widest drawn callee of the selected frame
+    fn select_child(&mut self) {
+        let depth = self.selected.len();
+        let child = self
+            .frames
+            .iter()
+            .filter(|frame| frame.path.len() == depth + 1 && frame.path.starts_with(&self.selected))
+            .max_by_key(|frame| frame.area.width);
+        if let Some(frame) = child {
+            self.selected = frame.path.clone();
+        }
+    }
+
+    fn select_parent(&mut self) {
+        if self.selected.len() > self.zoom.len().max(1) {
+            self.selected.pop();
+        }
+    }
+
+    /// Lay out `nodes` from left to right in `x0..x1` (in columns) of the row
+    /// `y`, and their callees below them
+    #[allow(clippy::too_many_arguments)]
+    fn layout<'a>(
+        frames: &mut Vec<FlameFrame>,
+        nodes: impl Iterator<Item = &'a FtraceNode>,
+        path: &mut Vec<usize>,
+        parent_time: Duration,
+        x0: f64,
+        x1: f64,
+        y: u16,
+        area: Rect,
+    ) {
+        if y >= area.bottom() || parent_time.is_zero() {
+            return;
+        }
+        let scale = (x1 - x0) / parent_time.as_secs_f64();
+        let mut x = x0;
+        for (idx, node) in nodes.enumerate() {
+            let time = node.time().unwrap_or_default();
+            let end = x + time.as_secs_f64() * scale;
+            path.push(idx);
+            Self::layout_node(frames, node, path, x, end, y, area);
+            path.pop();
+            x = end;
+        }
+    }
+
+    fn layout_node(
+        frames: &mut Vec<FlameFrame>,
+        node: &FtraceNode,
+        path: &mut Vec<usize>,
+        x0: f64,
+        x1: f64,
+        y: u16,
+        area: Rect,
+    ) {
+        let left = x0.round() as u16;
+        let right = x1.round() as u16;
+        // Too narrow to be drawn, and so are all of its callees
+        if right <= left {
+            return;
+        }
+        frames.push(FlameFrame {
+            path: path.clone(),
+            area: Rect::new(area.x + left, y, right - left, 1),
+        });
+        let time = node.time().unwrap_or_default();
+        Self::layout(frames, node.children(), path, time, x0, x1, y + 1, area);
+    }
+
+    fn info_line(&self) -> Line<'static> {
+        let theme = &self.config.theme;
+        let path = self.hovered.as_ref().unwrap_or(&self.selected);
+        let Some(node) = self.node(path) else {
+            return Line::raw("");
+        };
+        let time = node.time().unwrap_or_default();
+        let total = self
+            .tree
+            .as_ref()
+            .map(|tree| tree.total_time())
+            .unwrap_or_default();
+        let ratio = if total.is_zero() {
+            Span::styled("[n/a of trace]", theme.self_time_style())
+        } else {
+            let ratio = time.as_nanos() as f64 / total.as_nanos() as f64 * 100.0;
+            Span::styled(format!("[{:.2}% of trace]", ratio), theme.ratio_style())
+        };
+        Line::from_iter([
+            Span::styled(format!("{}()", node.name()), theme.name_style()),
+            Span::raw(" "),
+            Span::styled(format_duration(time), theme.time_style()),
+            Span::raw(" "),
+            Span::styled(
+                format!(
+                    "(self: {})",
+                    format_duration(node.self_time().unwrap_or_default())
+                ),
+                theme.self_time_style(),
+            ),
+            Span::raw(" "),
+            ratio,
+        ])
+    }
+}
+
+impl Component<AppMsg> for FlameGraphComponent {
+    fn handle(&mut self, event: Event<AppMsg>) {
+        match event {
+            Event::Key(key_event) if key_event.kind != KeyEventKind::Release => {
+                self.hovered = None;
+                match self.config.keymap.action(&key_event) {
+                    Some(Action::Up) => {
+                        self.select_parent();
+                    }
+                    Some(Action::Down) => {
+                        self.select_child();
+                    }
+                    Some(Action::Left) => {
+                        self.select_sibling(false);
+                    }
+                    Some(Action::Right) => {
+                        self.select_sibling(true);
+                    }
+                    Some(Action::Toggle) => {
+                        self.zoom_to(self.selected.clone());
+                    }
+                    Some(Action::Back) => {
+                        self.zoom_out();
+                    }
+                    Some(Action::First) => {
+                        self.zoom.clear();
+                    }
+                    _ => (),
+                }
+            }
+            Event::Mouse(mouse_event) => {
+                let position = Position::new(mouse_event.column, mouse_event.row);
+                let hit = self.frame_at(position).map(|frame| frame.path.clone());
+                match mouse_event.kind {
+                    MouseEventKind::Moved => {
+                        self.hovered = hit;
+                    }
+                    // Clicking the selected frame zooms into it
+                    MouseEventKind::Down(MouseButton::Left) => match hit {
+                        Some(path) if path == self.selected => self.zoom_to(path),
+                        Some(path) => self.selected = path,
+                        None => (),
+                    },
+                    MouseEventKind::ScrollUp => {
+                        if let Some(path) = hit {
+                            self.zoom_to(path);
+                        }
+                    }
+                    MouseEventKind::ScrollDown => {
+                        self.zoom_out();
+                    }
+                    _ => (),
+                }
+            }
+            Event::Message(AppMsg::UpdateTree(tree)) => {
+                // Paths stay valid as the tree only changes in its symbols
+                self.tree = Some(tree);
+            }
+            _ => (),
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(" Flame graph ")
+            .title_alignment(Alignment::Center)
+            .border_type(BorderType::Rounded);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        let [graph_area, info_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner);
+
+        self.frames.clear();
+        let Some(tree) = self.tree.clone() else {
+            frame.render_widget(Paragraph::new("Loading..."), graph_area);
+            return;
+        };
+        let width = graph_area.width as f64;
+        let mut path = self.zoom.clone();
+        match tree.node_at(&self.zoom) {
+            Some(node) => Self::layout_node(
+                &mut self.frames,
+                node,
+                &mut path,
+                0.0,
+                width,
+                graph_area.y,
+                graph_area,
+            ),
+            None => Self::layout(
+                &mut self.frames,
+                tree.children(),
+                &mut path,
+                tree.total_time(),
+                0.0,
+                width,
+                graph_area.y,
+                graph_area,
+            ),
+        }
+        if self.selected_frame().is_none() {
+            self.selected = self
+                .frames
+                .first()
+                .map(|frame| frame.path.clone())
+                .unwrap_or_default();
+        }
+
+        let buf = frame.buffer_mut();
+        for flame_frame in &self.frames {
+            let Some(node) = tree.node_at(&flame_frame.path) else {
+                continue;
+            };
+            let name = node.name();
+            // Calls of a function share a color whatever their offset
+            let mut style = Style::new()
+                .fg(Color::Black)
+                .bg(frame_color(&node.function()));
+            if flame_frame.path == self.selected {
+                style = style.patch(self.config.theme.highlight_style());
+            }
+            // Leave a gap between adjacent frames
+            let rect = Rect {
+                width: flame_frame.area.width.saturating_sub(1).max(1),
+                ..flame_frame.area
+            };
+            buf.set_style(rect, style);
+            // Single letters of narrow frames would only be noise
+            if rect.width >= 3 {
+                let label: String = name.chars().take(rect.width as usize).collect();
+                buf.set_string(rect.x, rect.y, label, style);
+            }
+        }
+        frame.render_widget(Paragraph::new(self.info_line()), info_area);
+    }
+}
+
+/// Warm color derived from the function name, so a function keeps its color
+pub(super) fn frame_color(name: &str) -> Color {
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    let hash = hasher.finish();
+    let r = 205 + (hash % 50) as u8;
+    let g = ((hash >> 8) % 230) as u8;
+    let b = ((hash >> 16) % 55) as u8;
+    Color::Rgb(r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ftrace::test_util::node;
+
+    fn frames(tree: &FtraceTree, area: Rect) -> Vec<(Vec<usize>, Rect)> {
+        let mut frames = Vec::new();
+        FlameGraphComponent::layout(
+            &mut frames,
+            tree.children(),
+            &mut Vec::new(),
+            tree.total_time(),
+            0.0,
+            f64::from(area.width),
+            area.y,
+            area,
+        );
+        frames
+            .into_iter()
+            .map(|frame| (frame.path, frame.area))
+            .collect()
+    }
+
+    #[test]
+    fn test_layout() {
+        let tree = FtraceTree::new(
+            String::new(),
+            vec![node(
+                0x10,
+                1000,
+                vec![
+                    node(0x20, 500, vec![node(0x50, 250, vec![])]),
+                    node(0x30, 499, vec![]),
+                    node(0x40, 1, vec![node(0x60, 1, vec![])]),
+                ],
+            )],
+        );
+        let area = Rect::new(2, 5, 100, 10);
+        // The last callee and its own callee are too narrow to be drawn
+        assert_eq!(
+            frames(&tree, area),
+            [
+                (vec![0], Rect::new(2, 5, 100, 1)),
+                (vec![0, 0], Rect::new(2, 6, 50, 1)),
+                (vec![0, 0, 0], Rect::new(2, 7, 25, 1)),
+                (vec![0, 1], Rect::new(52, 6, 50, 1)),
+            ]
+        );
+
+        // Rows below the area are left out
+        let frames = frames(&tree, Rect::new(2, 5, 100, 2));
+        assert_eq!(frames.len(), 3);
+        assert!(frames.iter().all(|(_, area)| area.y < 7));
+    }
+}
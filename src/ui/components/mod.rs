@@ -1,9 +1,25 @@
 use crate::ui::event::Event;
 use ratatui::{Frame, layout::Rect};
 
+mod bookmark_list;
+mod butterfly;
+mod flame_graph;
+mod node_details;
+mod prompt;
+mod stats_table;
+mod timeline;
+mod trace_info;
 mod trace_tree;
 
-pub use trace_tree::TraceTreeComponent;
+pub use bookmark_list::{BookmarkList, BookmarkListResult};
+pub use butterfly::ButterflyComponent;
+pub use flame_graph::FlameGraphComponent;
+pub use node_details::NodeDetailsComponent;
+pub use prompt::{Completion, Prompt, PromptResult};
+pub use stats_table::StatsTableComponent;
+pub use timeline::TimelineComponent;
+pub use trace_info::{TraceInfoComponent, TraceSummary};
+pub use trace_tree::{TraceTreeComponent, TreeColumn, TreeSession};
 
 pub trait Component<Msg> {
     fn handle(&mut self, event: Event<Msg>);
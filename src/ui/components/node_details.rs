@@ -0,0 +1,255 @@
+use super::{Component, TraceSummary};
+use crate::{
+    app::AppMsg,
+    config::Config,
+    ftrace::{FtraceNode, FtraceTree},
+    symbol::region::LoadInfo,
+    ui::event::Event,
+    utils::format_duration,
+};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Paragraph, Wrap},
+};
+use std::{sync::Arc, time::Duration};
+
+/// The node shown, with the figures that need a walk over the tree
+#[derive(Debug)]
+struct Selection {
+    tree: Arc<FtraceTree>,
+    path: Vec<usize>,
+    descendants: usize,
+    /// Position of the call among all calls of its function, the longest
+    /// first
+    rank: usize,
+    /// Number of calls of the function
+    function_calls: usize,
+}
+
+/// Everything known about the node selected in the tree
+#[derive(Debug)]
+pub struct NodeDetailsComponent {
+    config: Arc<Config>,
+    summary: Option<Arc<TraceSummary>>,
+    selection: Option<Selection>,
+}
+
+impl NodeDetailsComponent {
+    pub fn new(config: Arc<Config>) -> Self {
+        Self {
+            config,
+            summary: None,
+            selection: None,
+        }
+    }
+
+    /// Show the node at `path` of `tree`, or nothing if there is no node
+    pub fn select(&mut self, selected: Option<(Arc<FtraceTree>, Vec<usize>)>) {
+        let Some((tree, path)) = selected else {
+            self.selection = None;
+            return;
+        };
+        if let Some(selection) = &self.selection
+            && Arc::ptr_eq(&selection.tree, &tree)
+            && selection.path == path
+        {
+            return;
+        }
+        self.selection = tree.node_at(&path).map(|node| {
+            let time = node.time().unwrap_or_default();
+            let function = node.function();
+            let (rank, function_calls) = tree
+                .dfs_iter()
+                .filter(|other| other.function() == function)
+                .fold((1, 0), |(rank, calls), other| {
+                    let longer = other.time().unwrap_or_default() > time;
+                    (rank + usize::from(longer), calls + 1)
+                });
+            Selection {
+                descendants: node.descendants(),
+                rank,
+                function_calls,
+                tree: Arc::clone(&tree),
+                path,
+            }
+        });
+    }
+
+    fn lines(&self, selection: &Selection, node: &FtraceNode) -> Vec<Line<'static>> {
+        let theme = &self.config.theme;
+        let field = |label: &str, value: Span<'static>| {
+            Line::from_iter([
+                Span::styled(format!("{:<12}", label), theme.header_style()),
+                value,
+            ])
+        };
+        let time = |time: Option<Duration>| match time {
+            Some(time) => Span::styled(
+                format_duration(time).trim_start().to_string(),
+                theme.time_style(),
+            ),
+            None => Span::styled("unknown", theme.self_time_style()),
+        };
+        let unknown = || Span::styled("unknown", theme.self_time_style());
+
+        let symbol_info = self.summary.as_ref().map(|summary| &summary.symbol_info);
+        let load_info = symbol_info.and_then(|info| info.find_by_addr(node.func()));
+        let (elf, region) = match &load_info {
+            Some(LoadInfo::TrustedApp(info)) => {
+                let kind = match symbol_info.and_then(|info| info.ta_uuid) {
+                    Some(uuid) if uuid != info.uuid => "Library",
+                    _ => "TA",
+                };
+                // Without a region table the whole image is one region of
+                // unknown size
+                let region = match info.size {
+                    0 => unknown(),
+                    _ => Span::raw(format!(
+                        "{} (0x{:x}, size 0x{:x})",
+                        info.flags, info.va, info.size
+                    )),
+                };
+                (Span::raw(format!("{} {}", kind, info.uuid)), region)
+            }
+            Some(LoadInfo::Tee(_)) => (Span::raw("TEE core"), Span::raw("-")),
+            None => (unknown(), unknown()),
+        };
+        let elf_path = match &load_info {
+            Some(LoadInfo::TrustedApp(info)) => self
+                .summary
+                .as_ref()
+                .and_then(|summary| summary.elf_paths.get(&info.elf_idx).cloned().flatten()),
+            Some(LoadInfo::Tee(_)) => self
+                .summary
+                .as_ref()
+                .and_then(|summary| summary.tee_elf_path.clone()),
+            None => None,
+        };
+        let relative = load_info
+            .as_ref()
+            .and_then(|info| info.calculate_reladdr(node.func()))
+            .map(|addr| Span::raw(format!("0x{:x}", addr)))
+            .unwrap_or_else(unknown);
+        let symbol = match node.symbol() {
+            Some(symbol) => Span::styled(
+                format!("{} (0x{:x}, size 0x{:x})", symbol, symbol.addr, symbol.size),
+                theme.name_style(),
+            ),
+            None => unknown(),
+        };
+        let location = node
+            .symbol()
+            .and_then(|symbol| symbol.location.clone())
+            .map(Span::raw)
+            .unwrap_or_else(unknown);
+
+        vec![
+            field("Function", Span::styled(node.name(), theme.name_style())),
+            field("Address", Span::raw(format!("0x{:016x}", node.func()))),
+            field("Relative", relative),
+            field("ELF", elf),
+            field(
+                "File",
+                elf_path
+                    .map(|path| Span::raw(path.display().to_string()))
+                    .unwrap_or_else(unknown),
+            ),
+            field("Region", region),
+            field("Symbol", symbol),
+            field("Source", location),
+            Line::default(),
+            field("Depth", Span::raw(node.depth().to_string())),
+            field("Calls", Span::raw(node.calls().to_string())),
+            field("Total", time(node.time())),
+            field("Self", time(node.self_time())),
+            field("Children", time(Some(node.children_time()))),
+            field(
+                "Callees",
+                Span::raw(format!(
+                    "{} direct, {} in total",
+                    node.children().count(),
+                    selection.descendants
+                )),
+            ),
+            field(
+                "Rank",
+                Span::raw(format!(
+                    "{} of {} calls of the function by total time",
+                    selection.rank, selection.function_calls
+                )),
+            ),
+        ]
+    }
+}
+
+impl Component<AppMsg> for NodeDetailsComponent {
+    fn handle(&mut self, event: Event<AppMsg>) {
+        if let Event::Message(AppMsg::UpdateTraceSummary(summary)) = event {
+            self.summary = Some(summary);
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(" Details ")
+            .border_type(BorderType::Rounded);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let Some(selection) = &self.selection else {
+            frame.render_widget(Paragraph::new("No call selected"), inner);
+            return;
+        };
+        let Some(node) = selection.tree.node_at(&selection.path) else {
+            return;
+        };
+        let paragraph = Paragraph::new(self.lines(selection, node)).wrap(Wrap { trim: false });
+        frame.render_widget(paragraph, inner);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ftrace::test_util::node;
+
+    #[test]
+    fn test_select() {
+        let tree = Arc::new(FtraceTree::new(
+            String::new(),
+            vec![node(
+                0x10,
+                1000,
+                vec![
+                    node(0x20, 300, vec![node(0x30, 10, vec![])]),
+                    node(0x20, 500, vec![]),
+                    node(0x40, 100, vec![node(0x20, 400, vec![])]),
+                ],
+            )],
+        ));
+        let mut component = NodeDetailsComponent::new(Arc::new(Config::default()));
+        let figures = |component: &NodeDetailsComponent| {
+            component.selection.as_ref().map(|selection| {
+                (
+                    selection.rank,
+                    selection.function_calls,
+                    selection.descendants,
+                )
+            })
+        };
+
+        component.select(Some((Arc::clone(&tree), vec![0, 0])));
+        assert_eq!(figures(&component), Some((3, 3, 1)));
+        component.select(Some((Arc::clone(&tree), vec![0, 1])));
+        assert_eq!(figures(&component), Some((1, 3, 0)));
+        component.select(Some((Arc::clone(&tree), vec![0])));
+        assert_eq!(figures(&component), Some((1, 1, 5)));
+
+        component.select(Some((Arc::clone(&tree), vec![0, 3])));
+        assert_eq!(figures(&component), None);
+        component.select(None);
+        assert_eq!(figures(&component), None);
+    }
+}
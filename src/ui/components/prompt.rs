@@ -0,0 +1,257 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::{Position, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, Paragraph},
+};
+use std::{
+    fs,
+    path::{MAIN_SEPARATOR, Path},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Completion {
+    None,
+    Path,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PromptResult {
+    Pending,
+    Submit(String),
+    Cancel,
+}
+
+#[derive(Debug)]
+pub struct Prompt<K> {
+    kind: K,
+    title: String,
+    input: String,
+    /// Cursor position in characters
+    cursor: usize,
+    completion: Completion,
+    candidates: Vec<String>,
+}
+
+impl<K: Copy> Prompt<K> {
+    pub fn new(kind: K, title: impl Into<String>) -> Self {
+        Self {
+            kind,
+            title: format!(" {} ", title.into()),
+            input: String::new(),
+            cursor: 0,
+            completion: Completion::None,
+            candidates: Vec::new(),
+        }
+    }
+
+    pub fn with_completion(mut self, completion: Completion) -> Self {
+        self.completion = completion;
+        self
+    }
+
+    /// Start with `input` already entered
+    pub fn with_input(mut self, input: impl Into<String>) -> Self {
+        self.input = input.into();
+        self.cursor = self.input.chars().count();
+        self
+    }
+
+    pub fn kind(&self) -> K {
+        self.kind
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn handle_key(&mut self, key_event: KeyEvent) -> PromptResult {
+        if key_event.kind == KeyEventKind::Release {
+            return PromptResult::Pending;
+        }
+        match key_event.code {
+            KeyCode::Esc => return PromptResult::Cancel,
+            KeyCode::Enter => return PromptResult::Submit(self.input.clone()),
+            KeyCode::Tab => {
+                self.complete();
+                return PromptResult::Pending;
+            }
+            KeyCode::Char('u') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.input.clear();
+                self.cursor = 0;
+            }
+            KeyCode::Char(c) => {
+                let idx = self.byte_index();
+                self.input.insert(idx, c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let idx = self.byte_index();
+                self.input.remove(idx);
+            }
+            KeyCode::Delete if self.cursor < self.input.chars().count() => {
+                let idx = self.byte_index();
+                self.input.remove(idx);
+            }
+            KeyCode::Left => {
+                self.cursor = self.cursor.saturating_sub(1);
+            }
+            KeyCode::Right => {
+                self.cursor = (self.cursor + 1).min(self.input.chars().count());
+            }
+            KeyCode::Home => {
+                self.cursor = 0;
+            }
+            KeyCode::End => {
+                self.cursor = self.input.chars().count();
+            }
+            _ => (),
+        }
+        self.candidates.clear();
+        PromptResult::Pending
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let mut block = Block::bordered()
+            .title(self.title.as_str())
+            .border_type(BorderType::Rounded);
+        if !self.candidates.is_empty() {
+            block = block.title_bottom(Line::styled(
+                format!(" {} ", self.candidates.join("  ")),
+                Style::new().fg(Color::DarkGray),
+            ));
+        }
+        let inner = block.inner(area);
+        let paragraph = Paragraph::new(Line::from(Span::raw(self.input.as_str()))).block(block);
+        frame.render_widget(Clear, area);
+        frame.render_widget(paragraph, area);
+        frame.set_cursor_position(Position::new(
+            inner.x + (self.cursor as u16).min(inner.width.saturating_sub(1)),
+            inner.y,
+        ));
+    }
+
+    fn byte_index(&self) -> usize {
+        self.input
+            .char_indices()
+            .nth(self.cursor)
+            .map(|(i, _)| i)
+            .unwrap_or(self.input.len())
+    }
+
+    fn complete(&mut self) {
+        match self.completion {
+            Completion::None => (),
+            Completion::Path => {
+                let (completed, candidates) = complete_path(&self.input);
+                self.input = completed;
+                self.cursor = self.input.chars().count();
+                self.candidates = candidates;
+            }
+        }
+    }
+}
+
+/// Complete the last path component of `input` against the file system.
+///
+/// Returns the completed input and, when the completion is ambiguous, the
+/// names of all matching entries.
+fn complete_path(input: &str) -> (String, Vec<String>) {
+    let (dir_part, prefix) = match input.rfind(MAIN_SEPARATOR) {
+        Some(i) => input.split_at(i + 1),
+        None => ("", input),
+    };
+    let dir = if dir_part.is_empty() {
+        Path::new(".")
+    } else {
+        Path::new(dir_part)
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (input.to_owned(), Vec::new());
+    };
+
+    let mut matches: Vec<(String, bool)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let is_dir = entry.path().is_dir();
+            name.starts_with(prefix).then_some((name, is_dir))
+        })
+        .collect();
+    matches.sort();
+
+    match matches.as_slice() {
+        [] => (input.to_owned(), Vec::new()),
+        [(name, is_dir)] => {
+            let mut completed = format!("{}{}", dir_part, name);
+            if *is_dir {
+                completed.push(MAIN_SEPARATOR);
+            }
+            (completed, Vec::new())
+        }
+        _ => {
+            let common = matches
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .reduce(common_prefix)
+                .unwrap_or(prefix);
+            let candidates = matches.iter().map(|(name, _)| name.clone()).collect();
+            (format!("{}{}", dir_part, common), candidates)
+        }
+    }
+}
+
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+    let len = a
+        .char_indices()
+        .zip(b.chars())
+        .find(|((_, ca), cb)| ca != cb)
+        .map(|((i, _), _)| i)
+        .unwrap_or_else(|| a.len().min(b.len()));
+    &a[..len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::MAIN_SEPARATOR_STR;
+
+    #[test]
+    fn test_common_prefix() {
+        assert_eq!(common_prefix("ta.elf", "ta.map"), "ta.");
+        assert_eq!(common_prefix("ta", "ta.elf"), "ta");
+        assert_eq!(common_prefix("ta.elf", "ta"), "ta");
+        assert_eq!(common_prefix("été", "étage"), "ét");
+        assert_eq!(common_prefix("ta", "tee"), "t");
+        assert_eq!(common_prefix("ta", "lib"), "");
+    }
+
+    #[test]
+    fn test_complete_path() {
+        let dir = std::env::temp_dir().join(format!("ftrace-complete-{}", std::process::id()));
+        fs::create_dir_all(dir.join("out")).unwrap();
+        fs::write(dir.join("ta.elf"), "").unwrap();
+        fs::write(dir.join("ta.map"), "").unwrap();
+        let input = |name: &str| format!("{}{}{}", dir.display(), MAIN_SEPARATOR, name);
+
+        // Ambiguous names complete to their common prefix
+        let (completed, candidates) = complete_path(&input("t"));
+        assert_eq!(completed, input("ta."));
+        assert_eq!(candidates, ["ta.elf", "ta.map"]);
+        // A single match completes, with a separator for a directory
+        assert_eq!(complete_path(&input("ta.e")), (input("ta.elf"), Vec::new()));
+        assert_eq!(
+            complete_path(&input("o")),
+            (input("out") + MAIN_SEPARATOR_STR, Vec::new())
+        );
+        // Nothing to complete
+        assert_eq!(complete_path(&input("x")), (input("x"), Vec::new()));
+        let missing = input("missing/t");
+        assert_eq!(complete_path(&missing), (missing.clone(), Vec::new()));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,280 @@
+use super::Component;
+use crate::{
+    app::AppMsg,
+    config::Config,
+    ftrace::{FunctionStats, function_stats},
+    ui::{event::Event, keymap::Action},
+    utils::format_duration,
+};
+use crossterm::event::{KeyEventKind, MouseButton, MouseEventKind};
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Rect},
+    text::Line,
+    widgets::{Block, BorderType, Cell, Row, Table, TableState},
+};
+use std::{cmp::Ordering, sync::Arc, time::Duration};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatsColumn {
+    Name,
+    Calls,
+    Total,
+    SelfTime,
+    Min,
+    Max,
+    Mean,
+    P50,
+    P99,
+}
+
+impl StatsColumn {
+    const ALL: [StatsColumn; 9] = [
+        Self::Name,
+        Self::Calls,
+        Self::Total,
+        Self::SelfTime,
+        Self::Min,
+        Self::Max,
+        Self::Mean,
+        Self::P50,
+        Self::P99,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::Name => "Function",
+            Self::Calls => "Calls",
+            Self::Total => "Total",
+            Self::SelfTime => "Self",
+            Self::Min => "Min",
+            Self::Max => "Max",
+            Self::Mean => "Mean",
+            Self::P50 => "P50",
+            Self::P99 => "P99",
+        }
+    }
+
+    fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|c| *c == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    fn duration(self, stats: &FunctionStats) -> Option<Duration> {
+        match self {
+            Self::Name | Self::Calls => None,
+            Self::Total => Some(stats.total_time),
+            Self::SelfTime => Some(stats.self_time),
+            Self::Min => Some(stats.min),
+            Self::Max => Some(stats.max),
+            Self::Mean => Some(stats.mean),
+            Self::P50 => Some(stats.p50),
+            Self::P99 => Some(stats.p99),
+        }
+    }
+
+    fn compare(self, a: &FunctionStats, b: &FunctionStats) -> Ordering {
+        match self {
+            Self::Name => a.name.cmp(&b.name),
+            Self::Calls => a.calls.cmp(&b.calls),
+            _ => self.duration(a).cmp(&self.duration(b)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct StatsTableComponent {
+    config: Arc<Config>,
+    stats: Vec<FunctionStats>,
+    /// Indices into `stats` of the shown rows, in display order
+    rows: Vec<usize>,
+    sort_column: StatsColumn,
+    descending: bool,
+    filter: String,
+    state: TableState,
+    area: Rect,
+}
+
+impl StatsTableComponent {
+    pub fn new(config: Arc<Config>) -> Self {
+        Self {
+            config,
+            stats: Vec::new(),
+            rows: Vec::new(),
+            sort_column: StatsColumn::Total,
+            descending: true,
+            filter: String::new(),
+            state: TableState::default(),
+            area: Rect::default(),
+        }
+    }
+
+    /// Only show functions whose name contains `filter`, ignoring case
+    pub fn set_filter(&mut self, filter: &str) {
+        self.filter = filter.to_lowercase();
+        self.refresh();
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// Name of the selected function
+    pub fn selected(&self) -> Option<&str> {
+        let row = *self.rows.get(self.state.selected()?)?;
+        Some(self.stats[row].name.as_str())
+    }
+
+    fn sort_by(&mut self, column: StatsColumn) {
+        self.sort_column = column;
+        // Names read best from A to Z, numbers from the largest
+        self.descending = column != StatsColumn::Name;
+        self.refresh();
+    }
+
+    /// Rebuild the shown rows, keeping the selected function selected
+    fn refresh(&mut self) {
+        let selected = self.selected().map(str::to_string);
+        let stats = &self.stats;
+        self.rows = (0..stats.len())
+            .filter(|&i| stats[i].name.to_lowercase().contains(&self.filter))
+            .collect();
+        let column = self.sort_column;
+        self.rows.sort_by(|&a, &b| {
+            let ordering = column.compare(&stats[a], &stats[b]);
+            let ordering = if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            };
+            // Ties are always listed by name from A to Z
+            ordering.then_with(|| stats[a].name.cmp(&stats[b].name))
+        });
+
+        let position = selected.and_then(|name| {
+            self.rows
+                .iter()
+                .position(|&row| self.stats[row].name == name)
+        });
+        match position {
+            Some(position) => self.state.select(Some(position)),
+            None if self.rows.is_empty() => self.state.select(None),
+            None => self.state.select(Some(0)),
+        }
+    }
+
+    fn header(&self) -> Row<'static> {
+        Row::new(StatsColumn::ALL.map(|column| {
+            let mut title = column.title().to_string();
+            if column == self.sort_column {
+                title.push_str(if self.descending { " ▼" } else { " ▲" });
+            }
+            Cell::from(title)
+        }))
+        .style(self.config.theme.header_style())
+    }
+
+    fn row(&self, stats: &FunctionStats) -> Row<'static> {
+        let theme = &self.config.theme;
+        let cells = StatsColumn::ALL.map(|column| match column {
+            StatsColumn::Name => Cell::from(stats.name.clone()).style(theme.name_style()),
+            StatsColumn::Calls => Cell::from(Line::from(stats.calls.to_string()).right_aligned()),
+            StatsColumn::SelfTime => {
+                Cell::from(format_duration(stats.self_time)).style(theme.self_time_style())
+            }
+            _ => Cell::from(format_duration(column.duration(stats).unwrap_or_default()))
+                .style(theme.time_style()),
+        });
+        Row::new(cells)
+    }
+}
+
+impl Component<AppMsg> for StatsTableComponent {
+    fn handle(&mut self, event: Event<AppMsg>) {
+        match event {
+            Event::Key(key_event) if key_event.kind != KeyEventKind::Release => {
+                match self.config.keymap.action(&key_event) {
+                    Some(Action::Up) => {
+                        self.state.select_previous();
+                    }
+                    Some(Action::Down) => {
+                        self.state.select_next();
+                    }
+                    Some(Action::First) => {
+                        self.state.select_first();
+                    }
+                    Some(Action::Last) => {
+                        self.state.select_last();
+                    }
+                    Some(Action::PageUp) => {
+                        self.state.scroll_up_by(10);
+                    }
+                    Some(Action::PageDown) => {
+                        self.state.scroll_down_by(10);
+                    }
+                    Some(Action::SortColumn) => {
+                        self.sort_by(self.sort_column.next());
+                    }
+                    Some(Action::ReverseSort) => {
+                        self.descending = !self.descending;
+                        self.refresh();
+                    }
+                    _ => (),
+                }
+            }
+            Event::Mouse(mouse_event) => match mouse_event.kind {
+                MouseEventKind::Down(MouseButton::Left) => {
+                    // Skip the border and the header row
+                    let first_row = self.area.y + 2;
+                    if mouse_event.row >= first_row
+                        && mouse_event.row < self.area.bottom().saturating_sub(1)
+                    {
+                        let row = self.state.offset() + (mouse_event.row - first_row) as usize;
+                        if row < self.rows.len() {
+                            self.state.select(Some(row));
+                        }
+                    }
+                }
+                MouseEventKind::ScrollDown => {
+                    self.state.scroll_down_by(3);
+                }
+                MouseEventKind::ScrollUp => {
+                    self.state.scroll_up_by(3);
+                }
+                _ => (),
+            },
+            Event::Message(AppMsg::UpdateTree(tree)) => {
+                self.stats = function_stats(&tree);
+                self.refresh();
+            }
+            _ => (),
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        self.area = area;
+        let mut block = Block::bordered()
+            .title(format!(
+                " Function statistics ({} of {}) ",
+                self.rows.len(),
+                self.stats.len()
+            ))
+            .title_alignment(Alignment::Center)
+            .border_type(BorderType::Rounded);
+        if !self.filter.is_empty() {
+            block = block.title_bottom(Line::raw(format!(" filter: {} ", self.filter)));
+        }
+
+        let rows = self.rows.iter().map(|&row| self.row(&self.stats[row]));
+        let widths = StatsColumn::ALL.map(|column| match column {
+            StatsColumn::Name => Constraint::Fill(1),
+            StatsColumn::Calls => Constraint::Length(8),
+            _ => Constraint::Length(10),
+        });
+        let table = Table::new(rows, widths)
+            .header(self.header())
+            .block(block)
+            .row_highlight_style(self.config.theme.highlight_style());
+        frame.render_stateful_widget(table, area, &mut self.state);
+    }
+}
@@ -0,0 +1,478 @@
+use super::{Component, flame_graph::frame_color};
+use crate::{
+    app::AppMsg,
+    config::Config,
+    ftrace::{FtraceNode, FtraceTree},
+    ui::{event::Event, keymap::Action},
+    utils::format_duration,
+};
+use crossterm::event::{KeyEventKind, MouseButton, MouseEventKind};
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Layout, Position, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Paragraph},
+};
+use std::{sync::Arc, time::Duration};
+
+/// Columns between two labels of the time axis
+const TICK_SPACING: u16 = 20;
+/// Shortest time span shown over the whole width
+const MIN_SPAN: Duration = Duration::from_nanos(10);
+
+/// A call drawn on screen
+#[derive(Clone, Debug)]
+struct TimelineBar {
+    /// Child indices leading to the node from the top level
+    path: Vec<usize>,
+    start: Duration,
+    area: Rect,
+}
+
+/// Calls drawn in their order on a time axis, one row per call depth.
+///
+/// The start of each call is estimated from the durations of the calls
+/// before it.
+#[derive(Debug)]
+pub struct TimelineComponent {
+    config: Arc<Config>,
+    tree: Option<Arc<FtraceTree>>,
+    /// Time since the start of the trace at the left edge
+    start: Duration,
+    /// Time shown over the whole width, zero to show the whole trace
+    span: Duration,
+    /// First call depth shown
+    first_row: usize,
+    selected: Option<Vec<usize>>,
+    hovered: Option<Vec<usize>>,
+    /// Column where a mouse drag was last seen
+    drag_column: Option<u16>,
+    /// Bars of the last render, for mouse hits
+    bars: Vec<TimelineBar>,
+    graph_area: Rect,
+}
+
+impl TimelineComponent {
+    pub fn new(config: Arc<Config>) -> Self {
+        Self {
+            config,
+            tree: None,
+            start: Duration::ZERO,
+            span: Duration::ZERO,
+            first_row: 0,
+            selected: None,
+            hovered: None,
+            drag_column: None,
+            bars: Vec::new(),
+            graph_area: Rect::default(),
+        }
+    }
+
+    /// Path of the selected call, if any
+    pub fn selected_path(&self) -> Option<&[usize]> {
+        self.selected.as_deref()
+    }
+
+    fn total_time(&self) -> Duration {
+        self.tree
+            .as_ref()
+            .map(|tree| tree.total_time())
+            .unwrap_or_default()
+    }
+
+    fn span(&self) -> Duration {
+        if self.span.is_zero() {
+            self.total_time()
+        } else {
+            self.span
+        }
+    }
+
+    /// Show `span` starting at `start`, kept within the trace
+    fn set_window(&mut self, start: Duration, span: Duration) {
+        let total = self.total_time();
+        self.span = span.clamp(MIN_SPAN, total.max(MIN_SPAN));
+        self.start = start.min(total.saturating_sub(self.span));
+    }
+
+    fn time_per_column(&self) -> f64 {
+        self.span().as_secs_f64() / self.graph_area.width.max(1) as f64
+    }
+
+    /// Time at the column `column` of the screen
+    fn time_at(&self, column: u16) -> Duration {
+        let columns = column.saturating_sub(self.graph_area.x) as f64;
+        self.start + Duration::from_secs_f64(columns * self.time_per_column())
+    }
+
+    fn pan(&mut self, columns: i32) {
+        let delta = Duration::from_secs_f64(columns.unsigned_abs() as f64 * self.time_per_column());
+        let start = if columns < 0 {
+            self.start.saturating_sub(delta)
+        } else {
+            self.start + delta
+        };
+        self.set_window(start, self.span());
+    }
+
+    /// Zoom by `factor`, keeping the time at the column `anchor` in place
+    fn zoom(&mut self, factor: f64, anchor: u16) {
+        let anchor_time = self.time_at(anchor);
+        let span = self.span().mul_f64(factor);
+        let ratio = (anchor_time - self.start).as_secs_f64() / self.span().as_secs_f64().max(1e-12);
+        let start = anchor_time.saturating_sub(span.mul_f64(ratio));
+        self.set_window(start, span);
+    }
+
+    fn zoom_to_selected(&mut self) {
+        let Some(bar) = self
+            .bars
+            .iter()
+            .find(|bar| Some(&bar.path) == self.selected.as_ref())
+        else {
+            return;
+        };
+        let start = bar.start;
+        let time = self
+            .node(&bar.path)
+            .and_then(|node| node.time())
+            .unwrap_or_default();
+        self.set_window(start, time);
+    }
+
+    fn node(&self, path: &[usize]) -> Option<&FtraceNode> {
+        self.tree.as_ref()?.node_at(path)
+    }
+
+    /// Lay out `nodes` starting at `offset`, and their callees below them
+    fn layout<'a>(
+        &mut self,
+        nodes: impl Iterator<Item = (Duration, &'a FtraceNode)>,
+        offset: Duration,
+        path: &mut Vec<usize>,
+        depth: usize,
+    ) {
+        let area = self.graph_area;
+        let window_end = self.start + self.span();
+        let scale = area.width as f64 / self.span().as_secs_f64();
+        for (idx, (node_offset, node)) in nodes.enumerate() {
+            let start = offset + node_offset;
+            let end = start + node.time().unwrap_or_default();
+            if start >= window_end {
+                break;
+            }
+            if end <= self.start {
+                continue;
+            }
+            let left = (start.saturating_sub(self.start).as_secs_f64() * scale).round() as u16;
+            let right = ((end.min(window_end) - self.start).as_secs_f64() * scale).round() as u16;
+            // Too narrow to be drawn, and so are all of its callees
+            if right <= left {
+                continue;
+            }
+
+            path.push(idx);
+            if let Some(row) = depth.checked_sub(self.first_row)
+                && row < area.height as usize
+            {
+                self.bars.push(TimelineBar {
+                    path: path.clone(),
+                    start,
+                    area: Rect::new(area.x + left, area.y + row as u16, right - left, 1),
+                });
+            }
+            if depth + 1 < self.first_row + area.height as usize {
+                self.layout(node.children_with_offsets(), start, path, depth + 1);
+            }
+            path.pop();
+        }
+    }
+
+    fn axis_line(&self, width: u16) -> Line<'static> {
+        let mut spans = Vec::new();
+        let mut column = 0;
+        while column + TICK_SPACING <= width {
+            let time = self.time_at(self.graph_area.x + column);
+            let label = format!("|{}", format_duration(time).trim_start());
+            spans.push(Span::raw(format!(
+                "{:width$}",
+                label,
+                width = TICK_SPACING as usize
+            )));
+            column += TICK_SPACING;
+        }
+        Line::from(spans).style(Style::new().fg(Color::DarkGray))
+    }
+
+    fn info_line(&self) -> Line<'static> {
+        let theme = &self.config.theme;
+        let Some(bar) = self
+            .hovered
+            .as_ref()
+            .or(self.selected.as_ref())
+            .and_then(|path| self.bars.iter().find(|bar| &bar.path == path))
+        else {
+            return Line::raw(format!(
+                "{} - {}",
+                format_duration(self.start).trim_start(),
+                format_duration(self.start + self.span()).trim_start()
+            ));
+        };
+        let Some(node) = self.node(&bar.path) else {
+            return Line::raw("");
+        };
+        Line::from_iter([
+            Span::styled(format!("{}()", node.name()), theme.name_style()),
+            Span::raw(format!(" at ~{} ", format_duration(bar.start).trim_start())),
+            Span::styled(
+                format_duration(node.time().unwrap_or_default()),
+                theme.time_style(),
+            ),
+            Span::raw(" "),
+            Span::styled(
+                format!(
+                    "(self: {})",
+                    format_duration(node.self_time().unwrap_or_default())
+                ),
+                theme.self_time_style(),
+            ),
+        ])
+    }
+}
+
+impl Component<AppMsg> for TimelineComponent {
+    fn handle(&mut self, event: Event<AppMsg>) {
+        match event {
+            Event::Key(key_event) if key_event.kind != KeyEventKind::Release => {
+                self.hovered = None;
+                let center = self.graph_area.x + self.graph_area.width / 2;
+                match self.config.keymap.action(&key_event) {
+                    Some(Action::Left) => {
+                        self.pan(-(self.graph_area.width as i32 / 10).max(1));
+                    }
+                    Some(Action::Right) => {
+                        self.pan((self.graph_area.width as i32 / 10).max(1));
+                    }
+                    Some(Action::Up) => {
+                        self.first_row = self.first_row.saturating_sub(1);
+                    }
+                    Some(Action::Down) => {
+                        self.first_row += 1;
+                    }
+                    Some(Action::ZoomIn) => {
+                        self.zoom(0.5, center);
+                    }
+                    Some(Action::ZoomOut) => {
+                        self.zoom(2.0, center);
+                    }
+                    Some(Action::Toggle) => {
+                        self.zoom_to_selected();
+                    }
+                    Some(Action::First) => {
+                        self.start = Duration::ZERO;
+                        self.span = Duration::ZERO;
+                        self.first_row = 0;
+                    }
+                    _ => (),
+                }
+            }
+            Event::Mouse(mouse_event) => {
+                let position = Position::new(mouse_event.column, mouse_event.row);
+                let hit = self
+                    .bars
+                    .iter()
+                    .find(|bar| bar.area.contains(position))
+                    .map(|bar| bar.path.clone());
+                match mouse_event.kind {
+                    MouseEventKind::Moved => {
+                        self.hovered = hit;
+                    }
+                    MouseEventKind::Down(MouseButton::Left) => {
+                        self.drag_column = Some(mouse_event.column);
+                        if hit.is_some() {
+                            self.selected = hit;
+                        }
+                    }
+                    MouseEventKind::Drag(MouseButton::Left) => {
+                        if let Some(column) = self.drag_column {
+                            self.pan(column as i32 - mouse_event.column as i32);
+                        }
+                        self.drag_column = Some(mouse_event.column);
+                    }
+                    MouseEventKind::Up(_) => {
+                        self.drag_column = None;
+                    }
+                    MouseEventKind::ScrollUp => {
+                        self.zoom(0.8, mouse_event.column);
+                    }
+                    MouseEventKind::ScrollDown => {
+                        self.zoom(1.25, mouse_event.column);
+                    }
+                    _ => (),
+                }
+            }
+            Event::Message(AppMsg::UpdateTree(tree)) => {
+                self.tree = Some(tree);
+            }
+            _ => (),
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(" Timeline ")
+            .title_alignment(Alignment::Center)
+            .border_type(BorderType::Rounded);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        let [axis_area, graph_area, info_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+        self.graph_area = graph_area;
+
+        self.bars.clear();
+        let Some(tree) = self.tree.clone() else {
+            frame.render_widget(Paragraph::new("Loading..."), graph_area);
+            return;
+        };
+        if self.span().is_zero() {
+            return;
+        }
+        self.first_row = self
+            .first_row
+            .min(tree.max_depth().saturating_sub(1) as usize);
+        self.layout(
+            tree.children_with_offsets(),
+            Duration::ZERO,
+            &mut Vec::new(),
+            0,
+        );
+
+        frame.render_widget(Paragraph::new(self.axis_line(axis_area.width)), axis_area);
+        let buf = frame.buffer_mut();
+        for bar in &self.bars {
+            let Some(node) = tree.node_at(&bar.path) else {
+                continue;
+            };
+            let name = node.name();
+            // Calls of a function share a color whatever their offset
+            let mut style = Style::new()
+                .fg(Color::Black)
+                .bg(frame_color(&node.function()));
+            if Some(&bar.path) == self.selected.as_ref() {
+                style = style.patch(self.config.theme.highlight_style());
+            }
+            // Leave a gap between adjacent calls
+            let rect = Rect {
+                width: bar.area.width.saturating_sub(1).max(1),
+                ..bar.area
+            };
+            buf.set_style(rect, style);
+            if rect.width >= 3 {
+                let label: String = name.chars().take(rect.width as usize).collect();
+                buf.set_string(rect.x, rect.y, label, style);
+            }
+        }
+        frame.render_widget(Paragraph::new(self.info_line()), info_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ftrace::test_util::node;
+
+    fn component() -> TimelineComponent {
+        let tree = FtraceTree::new(
+            String::new(),
+            vec![
+                node(
+                    0x10,
+                    600,
+                    vec![node(0x20, 200, vec![]), node(0x30, 400, vec![])],
+                ),
+                node(0x40, 400, vec![]),
+            ],
+        );
+        let mut component = TimelineComponent::new(Arc::new(Config::default()));
+        component.tree = Some(Arc::new(tree));
+        component.graph_area = Rect::new(0, 0, 100, 5);
+        component
+    }
+
+    fn bars(component: &mut TimelineComponent) -> Vec<(Vec<usize>, Rect)> {
+        let tree = component.tree.clone().unwrap();
+        component.bars.clear();
+        component.layout(
+            tree.children_with_offsets(),
+            Duration::ZERO,
+            &mut Vec::new(),
+            0,
+        );
+        component
+            .bars
+            .iter()
+            .map(|bar| (bar.path.clone(), bar.area))
+            .collect()
+    }
+
+    #[test]
+    fn test_window() {
+        let mut component = component();
+        let window = |component: &TimelineComponent| {
+            (component.start.as_nanos(), component.span().as_nanos())
+        };
+        assert_eq!(window(&component), (0, 1000));
+
+        // Kept within the trace
+        component.set_window(Duration::ZERO, Duration::from_nanos(5));
+        assert_eq!(window(&component), (0, 10));
+        component.set_window(Duration::from_nanos(990), Duration::from_nanos(100));
+        assert_eq!(window(&component), (900, 100));
+        component.set_window(Duration::from_nanos(300), Duration::from_nanos(2000));
+        assert_eq!(window(&component), (0, 1000));
+
+        // The time at the anchor column stays in place
+        component.zoom(0.5, 50);
+        assert_eq!(window(&component), (250, 500));
+        component.zoom(0.5, 0);
+        assert_eq!(window(&component), (250, 250));
+        component.zoom(8.0, 0);
+        assert_eq!(window(&component), (0, 1000));
+    }
+
+    #[test]
+    fn test_layout() {
+        let mut component = component();
+        assert_eq!(
+            bars(&mut component),
+            [
+                (vec![0], Rect::new(0, 0, 60, 1)),
+                (vec![0, 0], Rect::new(0, 1, 20, 1)),
+                (vec![0, 1], Rect::new(20, 1, 40, 1)),
+                (vec![1], Rect::new(60, 0, 40, 1)),
+            ]
+        );
+
+        // Calls are offset from the start of the window and cut at its end
+        component.set_window(Duration::from_nanos(200), Duration::from_nanos(400));
+        assert_eq!(
+            bars(&mut component),
+            [
+                (vec![0], Rect::new(0, 0, 100, 1)),
+                (vec![0, 1], Rect::new(0, 1, 100, 1)),
+            ]
+        );
+
+        // Rows start at the first depth shown
+        component.first_row = 1;
+        assert_eq!(
+            bars(&mut component),
+            [(vec![0, 1], Rect::new(0, 0, 100, 1))]
+        );
+    }
+}
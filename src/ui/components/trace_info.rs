@@ -0,0 +1,222 @@
+use super::Component;
+use crate::{
+    app::AppMsg,
+    config::Config,
+    ftrace::{FtraceTree, Timebase},
+    symbol::info::SymbolInfo,
+    ui::{event::Event, theme::Theme},
+    utils::format_duration,
+};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Cell, Paragraph, Row, Table},
+};
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc, time::Duration};
+
+/// Everything known about a trace besides its call tree
+#[derive(Clone, Debug)]
+pub struct TraceSummary {
+    pub symbol_info: SymbolInfo,
+    /// ELF file found for each entry of the ELF list, keyed by its index
+    pub elf_paths: BTreeMap<usize, Option<PathBuf>>,
+    pub tee_elf_path: Option<PathBuf>,
+    pub entries: usize,
+    pub max_depth: u8,
+    pub total_time: Duration,
+    pub timebase: Timebase,
+}
+
+impl TraceSummary {
+    pub fn new(
+        tree: &FtraceTree,
+        symbol_info: SymbolInfo,
+        elf_paths: BTreeMap<usize, Option<PathBuf>>,
+        tee_elf_path: Option<PathBuf>,
+        timebase: Timebase,
+    ) -> Self {
+        Self {
+            symbol_info,
+            elf_paths,
+            tee_elf_path,
+            entries: tree.dfs_iter().count(),
+            max_depth: tree.max_depth(),
+            total_time: tree.total_time(),
+            timebase,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TraceInfoComponent {
+    config: Arc<Config>,
+    summary: Option<Arc<TraceSummary>>,
+}
+
+impl TraceInfoComponent {
+    pub fn new(config: Arc<Config>) -> Self {
+        Self {
+            config,
+            summary: None,
+        }
+    }
+
+    /// Height needed to show all information including the borders
+    pub fn height(&self) -> u16 {
+        match &self.summary {
+            Some(summary) => {
+                let elf_rows = summary.symbol_info.elf_list.len().max(1);
+                let region_rows = summary.symbol_info.regions.len().max(1);
+                // Summary, ELF table with spacing, region table and borders
+                (4 + (elf_rows + 2) + (region_rows + 1) + 2) as u16
+            }
+            None => 3,
+        }
+    }
+
+    fn summary_lines(&self, summary: &TraceSummary) -> Vec<Line<'static>> {
+        let theme = &self.config.theme;
+        let info = &summary.symbol_info;
+        let tee = match info.tee_load_addr {
+            Some(load_addr) => format!("0x{:016x}", load_addr),
+            None => "unknown".to_string(),
+        };
+        let ta = match (info.ta_uuid, info.ta_load_addr) {
+            (Some(uuid), Some(load_addr)) => format!("{} @ 0x{:016x}", uuid, load_addr),
+            _ => "unknown".to_string(),
+        };
+        vec![
+            Line::from_iter([
+                Span::styled("TEE load address  ", theme.header_style()),
+                Span::raw(tee),
+                Span::raw("  "),
+                path_span(
+                    info.tee_load_addr.map(|_| summary.tee_elf_path.as_ref()),
+                    theme,
+                ),
+            ]),
+            Line::from_iter([
+                Span::styled("Trusted app       ", theme.header_style()),
+                Span::raw(ta),
+            ]),
+            Line::from_iter([
+                Span::styled("Entries           ", theme.header_style()),
+                Span::raw(summary.entries.to_string()),
+                Span::styled("    Max depth ", theme.header_style()),
+                Span::raw(summary.max_depth.to_string()),
+                Span::styled("    Total time ", theme.header_style()),
+                Span::styled(
+                    format_duration(summary.total_time).trim_start().to_string(),
+                    theme.time_style(),
+                ),
+                Span::styled("    Timebase ", theme.header_style()),
+                Span::raw(format!("{} Hz", summary.timebase.hz())),
+            ]),
+        ]
+    }
+
+    fn elf_table(&self, summary: &TraceSummary) -> Table<'static> {
+        let theme = &self.config.theme;
+        let mut elf_list: Vec<_> = summary.symbol_info.elf_list.values().collect();
+        elf_list.sort_by_key(|elf| elf.idx);
+        let rows = elf_list.into_iter().map(|elf| {
+            let path = summary.elf_paths.get(&elf.idx).and_then(|p| p.as_ref());
+            Row::new([
+                Cell::from(format!("[{}]", elf.idx)),
+                Cell::from(elf.uuid.to_string()),
+                Cell::from(format!("0x{:016x}", elf.load_addr)),
+                Cell::from(path_span(Some(path), theme)),
+            ])
+        });
+        Table::new(
+            rows,
+            [
+                Constraint::Length(5),
+                Constraint::Length(36),
+                Constraint::Length(18),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["ELF", "UUID", "Load address", "File"]).style(theme.header_style()))
+    }
+
+    fn region_table(&self, summary: &TraceSummary) -> Table<'static> {
+        let theme = &self.config.theme;
+        let info = &summary.symbol_info;
+        let rows = info.regions.iter().enumerate().map(|(i, region)| {
+            let elf = match region.elf_idx {
+                Some(idx) => {
+                    let path = summary.elf_paths.get(&idx).and_then(|p| p.as_ref());
+                    Line::from_iter([
+                        Span::raw(format!("[{}] ", idx)),
+                        path_span(Some(path), theme),
+                    ])
+                }
+                None => Line::raw("-"),
+            };
+            Row::new([
+                Cell::from(i.to_string()),
+                Cell::from(format!("0x{:016x}", region.va)),
+                Cell::from(format!("0x{:016x}", region.pa)),
+                Cell::from(format!("0x{:08x}", region.size)),
+                Cell::from(region.flags.to_string()),
+                Cell::from(elf),
+            ])
+        });
+        Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Length(18),
+                Constraint::Length(18),
+                Constraint::Length(10),
+                Constraint::Length(5),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["#", "VA", "PA", "Size", "Flags", "ELF"]).style(theme.header_style()))
+    }
+}
+
+impl Component<AppMsg> for TraceInfoComponent {
+    fn handle(&mut self, event: Event<AppMsg>) {
+        if let Event::Message(AppMsg::UpdateTraceSummary(summary)) = event {
+            self.summary = Some(summary);
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(" Trace information ")
+            .border_type(BorderType::Rounded);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let Some(summary) = self.summary.clone() else {
+            frame.render_widget(Paragraph::new("Loading..."), inner);
+            return;
+        };
+        let elf_rows = summary.symbol_info.elf_list.len().max(1) as u16;
+        let [summary_area, elf_area, region_area] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Length(elf_rows + 2),
+            Constraint::Fill(1),
+        ])
+        .areas(inner);
+        frame.render_widget(Paragraph::new(self.summary_lines(&summary)), summary_area);
+        frame.render_widget(self.elf_table(&summary), elf_area);
+        frame.render_widget(self.region_table(&summary), region_area);
+    }
+}
+
+/// Show the path of a found ELF file, or whether it is missing.
+///
+/// `None` means no ELF file is expected at all.
+fn path_span(path: Option<Option<&PathBuf>>, theme: &Theme) -> Span<'static> {
+    match path {
+        Some(Some(path)) => Span::raw(path.display().to_string()),
+        Some(None) => Span::styled("missing", theme.missing_style()),
+        None => Span::raw("-"),
+    }
+}
@@ -1,97 +1,1371 @@
 use super::Component;
 use crate::{
     app::AppMsg,
-    ftrace::{FtraceNode, FtraceTree},
-    ui::event::Event,
+    config::Config,
+    diff::format_change,
+    ftrace::{
+        Change, Delta, Figures, FtraceNode, FtraceTree, SymbolFilter, Threshold, call_path,
+        call_path_figures, diff, function_figures,
+    },
+    ui::{event::Event, keymap::Action, theme::Theme},
+    utils::format_duration,
 };
-use crossterm::event::{KeyCode, MouseButton, MouseEventKind};
+use crossterm::event::{KeyEventKind, MouseButton, MouseEventKind};
 use ratatui::{
     Frame,
-    layout::{Alignment, Position, Rect},
-    style::{Color, Modifier, Style},
+    layout::{Alignment, Constraint, Layout, Position, Rect},
     text::{Line, Span, Text},
-    widgets::{Block, BorderType},
+    widgets::{Block, BorderType, Clear, Paragraph},
+};
+use serde::{Deserialize, Deserializer, Serialize, de::Error as _};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    hash::{DefaultHasher, Hash, Hasher},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
 };
-use std::time::Duration;
 use tui_tree_widget::{Tree, TreeItem, TreeState};
 
-#[derive(Debug, Default)]
+/// Identifier flag of the items grouping runs of identical sibling calls.
+///
+/// The rest of the identifier is the index of the first call of the run.
+const GROUP_FLAG: u64 = 1 << 63;
+/// Identifier of the item gathering the calls hidden by the threshold
+const HIDDEN_ID: u64 = u64::MAX;
+/// Threshold set when raising it while none is set
+const DEFAULT_THRESHOLD: Threshold = Threshold::Time(Duration::from_micros(1));
+/// Separator of the calls of a path, in the selection path, the focus
+/// breadcrumb and the bookmarks
+pub(super) const PATH_SEPARATOR: &str = " › ";
+/// Longest name of an ancestor in the selection path
+const MAX_SEGMENT_WIDTH: usize = 32;
+
+/// Order of sibling nodes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TreeSort {
+    #[default]
+    CallOrder,
+    TotalTime,
+    SelfTime,
+    Calls,
+    Name,
+}
+
+impl TreeSort {
+    fn next(self) -> Self {
+        match self {
+            Self::CallOrder => Self::TotalTime,
+            Self::TotalTime => Self::SelfTime,
+            Self::SelfTime => Self::Calls,
+            Self::Calls => Self::Name,
+            Self::Name => Self::CallOrder,
+        }
+    }
+
+    /// Sort `items` by the summed figures of their nodes, the largest first
+    fn sort<T>(self, items: &mut [T], nodes: impl Fn(&T) -> &[&FtraceNode]) {
+        let time = |item: &T| -> Duration {
+            nodes(item)
+                .iter()
+                .map(|node| node.time().unwrap_or_default())
+                .sum()
+        };
+        let self_time = |item: &T| -> Duration {
+            nodes(item)
+                .iter()
+                .map(|node| node.self_time().unwrap_or_default())
+                .sum()
+        };
+        let calls = |item: &T| -> usize { nodes(item).iter().map(|node| node.calls()).sum() };
+        let name = |item: &T| nodes(item).first().map(|node| node.name());
+        match self {
+            Self::CallOrder => (),
+            Self::TotalTime => items.sort_by_key(|item| std::cmp::Reverse(time(item))),
+            Self::SelfTime => items.sort_by_key(|item| std::cmp::Reverse(self_time(item))),
+            Self::Calls => items.sort_by_key(|item| std::cmp::Reverse(calls(item))),
+            Self::Name => items.sort_by_cached_key(name),
+        }
+    }
+}
+
+impl fmt::Display for TreeSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::CallOrder => "call order",
+            Self::TotalTime => "total time",
+            Self::SelfTime => "self time",
+            Self::Calls => "calls",
+            Self::Name => "name",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Right-aligned column of figures next to the names of the tree
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TreeColumn {
+    Total,
+    SelfTime,
+    /// Percentage of the time of the [`PercentBase`]
+    Percent,
+    /// Percentage of the total time of the trace, or of the focused node
+    OfRoot,
+    Calls,
+    Address,
+}
+
+/// Columns shown when none are configured
+const DEFAULT_COLUMNS: &[TreeColumn] = &[
+    TreeColumn::Total,
+    TreeColumn::SelfTime,
+    TreeColumn::Percent,
+    TreeColumn::Calls,
+];
+
+impl TreeColumn {
+    fn title(self, base: PercentBase) -> &'static str {
+        match self {
+            Self::Total => "Total",
+            Self::SelfTime => "Self",
+            Self::Percent => base.title(),
+            Self::OfRoot => "% Root",
+            Self::Calls => "Calls",
+            Self::Address => "Address",
+        }
+    }
+
+    fn width(self) -> u16 {
+        match self {
+            Self::Total | Self::SelfTime => 10,
+            Self::Percent | Self::OfRoot | Self::Calls => 8,
+            Self::Address => 18,
+        }
+    }
+
+    /// Text of the cell of a row, empty if the figure does not apply
+    fn cell(self, row: &RowFigures, theme: &Theme) -> Span<'static> {
+        let percent = |base: Option<Duration>| match base.filter(|base| !base.is_zero()) {
+            Some(base) => {
+                let ratio = row.time.as_nanos() as f64 / base.as_nanos() as f64 * 100.0;
+                Span::styled(format!("{:.2}%", ratio), theme.ratio_style())
+            }
+            None => Span::styled("n/a", theme.self_time_style()),
+        };
+        match self {
+            Self::Total => Span::styled(format_duration(row.time), theme.time_style()),
+            Self::SelfTime => match row.self_time {
+                Some(self_time) => {
+                    Span::styled(format_duration(self_time), theme.self_time_style())
+                }
+                None => Span::raw(""),
+            },
+            Self::Percent => percent(row.base_time),
+            Self::OfRoot => percent(Some(row.root_time)),
+            Self::Calls => Span::styled(row.calls.to_string(), theme.ratio_style()),
+            Self::Address => match row.addr {
+                Some(addr) => Span::styled(format!("{:#x}", addr), theme.self_time_style()),
+                None => Span::raw(""),
+            },
+        }
+    }
+}
+
+impl fmt::Display for TreeColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Total => "total",
+            Self::SelfTime => "self",
+            Self::Percent => "percent",
+            Self::OfRoot => "root",
+            Self::Calls => "calls",
+            Self::Address => "address",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for TreeColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "total" => Ok(Self::Total),
+            "self" => Ok(Self::SelfTime),
+            "percent" => Ok(Self::Percent),
+            "root" => Ok(Self::OfRoot),
+            "calls" => Ok(Self::Calls),
+            "address" | "addr" => Ok(Self::Address),
+            _ => Err(format!(
+                "unknown column `{}`, expected total, self, percent, root, calls or address",
+                s
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TreeColumn {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+/// Figures of a shown row, computed when rendering it
+#[derive(Clone, Debug)]
+struct RowFigures {
+    time: Duration,
+    /// Unset for the item gathering the hidden calls
+    self_time: Option<Duration>,
+    /// Time of the percentage base, unset outside of the anchor
+    base_time: Option<Duration>,
+    root_time: Duration,
+    calls: usize,
+    addr: Option<u64>,
+}
+
+/// Time the percentages of the tree are relative to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PercentBase {
+    /// The total time of the caller
+    Parent,
+    /// The total time of the trace, or of the focused node
+    Root,
+    /// The total time of a node picked as the anchor, for its callees
+    Anchor,
+}
+
+impl PercentBase {
+    fn next(self) -> Self {
+        match self {
+            Self::Parent => Self::Root,
+            Self::Root => Self::Anchor,
+            Self::Anchor => Self::Parent,
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::Parent => "% Parent",
+            Self::Root => "% Root",
+            Self::Anchor => "% Anchor",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TreeMode {
+    /// Every call as its own node
+    #[default]
+    Calls,
+    /// Calls with the same call path merged
+    Aggregated,
+}
+
+/// State of the tree saved between runs.
+///
+/// Items are identified by text, as TOML cannot hold the identifiers of
+/// groups: the index for calls, `g` and the index of the first call for
+/// groups and `hidden` for the calls hidden by the threshold, separated by
+/// `/` from the top level.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TreeSession {
+    mode: TreeMode,
+    filter: String,
+    threshold: Option<String>,
+    sort: TreeSort,
+    level_sorts: Vec<LevelSort>,
+    opened: Vec<String>,
+    selected: String,
+    offset: usize,
+}
+
+/// Order of the siblings of a single level
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct LevelSort {
+    /// Path of the caller of the siblings
+    level: Vec<usize>,
+    sort: TreeSort,
+}
+
+/// A level of the focus stack
+#[derive(Debug)]
+struct Focus {
+    /// Path of the node shown as the root
+    path: Vec<usize>,
+    /// State before focusing on the node, restored when leaving it
+    state: TreeState<u64>,
+}
+
+#[derive(Debug)]
 pub struct TraceTreeComponent {
+    config: Arc<Config>,
+    tree: Option<Arc<FtraceTree>>,
+    filter: SymbolFilter,
+    /// `tree` with `filter` applied, if there is any filter
+    filtered: Option<Arc<FtraceTree>>,
+    /// Calling context tree of the filtered tree, built when first shown
+    aggregated: Option<Arc<FtraceTree>>,
+    mode: TreeMode,
+    /// Trace to compare with, coloring the nodes by their change
+    baseline: Option<Arc<FtraceTree>>,
+    /// `baseline` with `filter` applied, if there is any filter
+    filtered_baseline: Option<Arc<FtraceTree>>,
+    /// Changes of the shown tree against the baseline, computed again on the
+    /// next rebuild once unset
+    deltas: Option<HashMap<String, Delta>>,
+    /// Nodes taking less time are gathered into one item per caller
+    threshold: Option<Threshold>,
+    /// Nodes focused on, the innermost one last
+    focus: Vec<Focus>,
+    /// Text searched in the names and addresses of the nodes
+    search: String,
+    /// Paths of the nodes matching `search` in depth-first order
+    matches: Vec<Vec<usize>>,
+    /// Order of the siblings on all levels
+    sort: TreeSort,
+    /// Orders of single levels overriding `sort`, keyed by the path of the
+    /// caller of the siblings
+    level_sorts: HashMap<Vec<usize>, TreeSort>,
+    /// Columns shown right of the names, in order
+    columns: Vec<TreeColumn>,
+    percent_base: PercentBase,
+    /// Path of the node the percentages are relative to with
+    /// [`PercentBase::Anchor`]
+    anchor: Option<Vec<usize>>,
+    /// Paths of the bookmarked calls in the unfiltered tree
+    bookmarks: Vec<Vec<usize>>,
+    /// Paths of the bookmarked calls in the shown tree
+    bookmarked: HashSet<Vec<usize>>,
     data: Vec<TreeItem<'static, u64>>,
+    /// Segments of the selection path drawn on the last render, with the
+    /// identifiers of their item, for clicks
+    path_segments: Vec<(Rect, Vec<u64>)>,
     title: String,
     state: TreeState<u64>,
+    /// State of the mode not shown, restored when switching back
+    other_state: TreeState<u64>,
+    /// Scroll position of a restored session, applied once there are items
+    /// to scroll
+    restored_offset: Option<usize>,
 }
 
 impl TraceTreeComponent {
-    pub fn new() -> Self {
+    pub fn new(config: Arc<Config>) -> Self {
         Self {
+            filter: config.filter.clone(),
+            columns: config
+                .columns
+                .clone()
+                .unwrap_or_else(|| DEFAULT_COLUMNS.to_vec()),
+            percent_base: PercentBase::Parent,
+            anchor: None,
+            bookmarks: Vec::new(),
+            bookmarked: HashSet::new(),
+            config,
+            tree: None,
+            filtered: None,
+            aggregated: None,
+            mode: TreeMode::Calls,
+            baseline: None,
+            filtered_baseline: None,
+            deltas: None,
+            threshold: None,
+            focus: Vec::new(),
+            search: String::new(),
+            matches: Vec::new(),
+            sort: TreeSort::CallOrder,
+            level_sorts: HashMap::new(),
             data: Vec::new(),
+            path_segments: Vec::new(),
             title: String::new(),
             state: TreeState::default(),
+            other_state: TreeState::default(),
+            restored_offset: None,
         }
     }
 
-    #[allow(unused)]
-    pub fn with_ftrace_tree(tree: &FtraceTree) -> Self {
-        TraceTreeComponent {
-            data: Self::build_tree_data(tree),
-            title: String::new(),
-            state: TreeState::default(),
+    /// State to restore on the next run of the trace
+    pub fn session(&self) -> TreeSession {
+        // Focusing is not restored, the state from before it is saved instead
+        let state = self.focus.first().map_or(&self.state, |focus| &focus.state);
+        let mut opened: Vec<_> = state.opened().iter().map(|ids| format_ids(ids)).collect();
+        opened.sort();
+        let mut level_sorts: Vec<_> = self
+            .level_sorts
+            .iter()
+            .map(|(level, sort)| LevelSort {
+                level: level.clone(),
+                sort: *sort,
+            })
+            .collect();
+        level_sorts.sort_by(|a, b| a.level.cmp(&b.level));
+        TreeSession {
+            mode: self.mode,
+            filter: self.filter.to_string(),
+            threshold: self.threshold.map(|threshold| threshold.to_string()),
+            sort: self.sort,
+            level_sorts,
+            opened,
+            selected: format_ids(state.selected()),
+            offset: state.get_offset(),
         }
     }
 
-    pub fn build_tree_data(tree: &FtraceTree) -> Vec<TreeItem<'static, u64>> {
-        let mut data = Vec::new();
+    /// Restore the state saved by [`Self::session`], before the trace is
+    /// loaded.
+    ///
+    /// A filter set in the config or on the command line is kept over the
+    /// saved one, in which case the mode, the sorted levels, the expanded
+    /// nodes and the selection are not restored as they refer to another
+    /// tree.
+    pub fn restore(&mut self, session: TreeSession) {
+        if self.filter.is_empty()
+            && let Ok(filter) = session.filter.parse()
+        {
+            self.filter = filter;
+        }
+        self.threshold = session
+            .threshold
+            .and_then(|threshold| threshold.parse().ok());
+        self.sort = session.sort;
+        if self.filter.to_string() != session.filter {
+            return;
+        }
+        self.level_sorts = session
+            .level_sorts
+            .into_iter()
+            .map(|level| (level.level, level.sort))
+            .collect();
+        self.mode = session.mode;
+        let mut state = TreeState::default();
+        for ids in session.opened.iter().filter_map(|ids| parse_ids(ids)) {
+            state.open(ids);
+        }
+        if let Some(selected) = parse_ids(&session.selected) {
+            state.select(selected);
+        }
+        self.state = state;
+        self.restored_offset = Some(session.offset);
+    }
 
-        let time = tree
-            .children()
-            .map(|node| node.time().unwrap_or_default())
-            .sum();
-        for (child_id, children) in tree.children().enumerate() {
-            data.push(build_ftrace_ui_tree(child_id as u64, children, time));
+    /// The calls left by the filter
+    fn calls_tree(&self) -> Option<&Arc<FtraceTree>> {
+        match self.filter.is_empty() {
+            true => self.tree.as_ref(),
+            false => self.filtered.as_ref(),
         }
+    }
 
-        data
+    /// The tree shown in the current mode
+    fn shown_tree(&self) -> Option<&FtraceTree> {
+        match self.mode {
+            TreeMode::Calls => self.calls_tree().map(Arc::as_ref),
+            TreeMode::Aggregated => self.aggregated.as_deref(),
+        }
     }
-}
 
-impl Component<AppMsg> for TraceTreeComponent {
-    fn handle(&mut self, event: Event<AppMsg>) {
-        match event {
-            Event::Key(key_event) => match key_event.code {
-                KeyCode::Up => {
-                    self.state.key_up();
+    /// The unfiltered tree
+    pub fn tree(&self) -> Option<&Arc<FtraceTree>> {
+        self.tree.as_ref()
+    }
+
+    /// Path in the unfiltered tree of the selected call, if a call is
+    /// selected
+    pub fn selected_call(&self) -> Option<Vec<usize>> {
+        if self.mode != TreeMode::Calls {
+            return None;
+        }
+        let tree = self.tree.as_ref()?;
+        let path = self.selected_path();
+        let path = match self.filter.is_empty() {
+            true => path,
+            false => tree.unfiltered_path(&self.filter, &path)?,
+        };
+        tree.node_at(&path).is_some().then_some(path)
+    }
+
+    /// Mark the calls at `paths` of the unfiltered tree as bookmarked
+    pub fn set_bookmarks(&mut self, paths: Vec<Vec<usize>>) {
+        self.bookmarks = paths;
+        self.update_bookmarked();
+        self.rebuild();
+    }
+
+    /// Map the bookmarks to the shown tree
+    fn update_bookmarked(&mut self) {
+        self.bookmarked = match &self.tree {
+            Some(tree) if self.mode == TreeMode::Calls && !self.filter.is_empty() => {
+                let excluded = |path: &[usize]| {
+                    tree.node_at(path)
+                        .is_some_and(|node| self.filter.excludes(&node.function()))
+                };
+                self.bookmarks
+                    .iter()
+                    .filter(|path| !excluded(path))
+                    .filter_map(|path| {
+                        // Shorter if the call is left out, as the path of its
+                        // closest ancestor left
+                        let kept = (1..=path.len())
+                            .filter(|&depth| !excluded(&path[..depth]))
+                            .count();
+                        let filtered = tree.filtered_path(&self.filter, path);
+                        (filtered.len() == kept).then_some(filtered)
+                    })
+                    .collect()
+            }
+            Some(_) if self.mode == TreeMode::Calls => self.bookmarks.iter().cloned().collect(),
+            _ => HashSet::new(),
+        };
+    }
+
+    /// The shown tree and the path of the selected node in it
+    pub fn selection(&self) -> Option<(Arc<FtraceTree>, Vec<usize>)> {
+        let tree = match self.mode {
+            TreeMode::Calls => self.calls_tree()?,
+            TreeMode::Aggregated => self.aggregated.as_ref()?,
+        };
+        Some((Arc::clone(tree), self.selected_path()))
+    }
+
+    pub fn selected_node(&self) -> Option<&FtraceNode> {
+        self.shown_tree()?.node_at(&self.selected_path())
+    }
+
+    /// Child indices leading to the selected node from the top level
+    fn selected_path(&self) -> Vec<usize> {
+        let selected = self.state.selected();
+        if selected.is_empty() {
+            return Vec::new();
+        }
+        // Groups stand for their first call when selected, otherwise the
+        // calls inside them are identified by their index among all siblings.
+        // The hidden calls come after all calls of their level, at an index
+        // matching no node.
+        let ids = selected
+            .iter()
+            .enumerate()
+            .filter(|&(depth, &id)| id & GROUP_FLAG == 0 || depth == selected.len() - 1)
+            .map(|(_, &id)| match id {
+                HIDDEN_ID => usize::MAX,
+                id => (id & !GROUP_FLAG) as usize,
+            });
+        self.focus_prefix().iter().copied().chain(ids).collect()
+    }
+
+    /// Path of the node shown as the root, empty if there is no focus
+    fn focus_path(&self) -> &[usize] {
+        self.focus
+            .last()
+            .map(|focus| focus.path.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Path of the caller of the focused node, leading to the shown items
+    fn focus_prefix(&self) -> &[usize] {
+        let path = self.focus_path();
+        &path[..path.len().saturating_sub(1)]
+    }
+
+    /// Show the selected node as the root, with the percentages relative to it
+    fn focus_selected(&mut self) {
+        let path = self.selected_path();
+        if path == self.focus_path() || self.selected_node().is_none() {
+            return;
+        }
+        let state = std::mem::take(&mut self.state);
+        self.focus.push(Focus { path, state });
+        self.rebuild();
+        let root = self.data.first().map(|item| vec![*item.identifier()]);
+        if let Some(root) = root {
+            self.state.open(root.clone());
+            self.state.select(root);
+        }
+    }
+
+    /// Names of the focused nodes, from the outermost one
+    fn breadcrumb(&self) -> Line<'static> {
+        let theme = &self.config.theme;
+        let mut spans = vec![Span::styled("All calls", theme.ratio_style())];
+        for focus in &self.focus {
+            let name = self
+                .shown_tree()
+                .and_then(|tree| tree.node_at(&focus.path))
+                .map(|node| node.name())
+                .unwrap_or_default();
+            spans.push(Span::raw(PATH_SEPARATOR));
+            spans.push(Span::styled(name, theme.name_style()));
+        }
+        Line::from(spans)
+    }
+
+    /// Identifiers and names of the selected item and its ancestors, from
+    /// the top level
+    fn selection_segments(&self) -> Vec<(Vec<u64>, String)> {
+        let Some(tree) = self.shown_tree() else {
+            return Vec::new();
+        };
+        let selected = self.state.selected();
+        let mut path = self.focus_prefix().to_vec();
+        let mut segments = Vec::new();
+        for (depth, &id) in selected.iter().enumerate() {
+            let last = depth == selected.len() - 1;
+            let name = match id {
+                HIDDEN_ID => "hidden calls".to_string(),
+                // Groups are followed by the call selected inside them
+                id if id & GROUP_FLAG != 0 && !last => continue,
+                id => {
+                    path.push((id & !GROUP_FLAG) as usize);
+                    match tree.node_at(&path) {
+                        Some(node) => node.name(),
+                        None => break,
+                    }
                 }
-                KeyCode::Down => {
-                    self.state.key_down();
+            };
+            segments.push((selected[..=depth].to_vec(), name));
+        }
+        segments
+    }
+
+    /// Draw the path to the selected item, leaving out the middle of it if it
+    /// does not fit
+    fn render_selection_path(&mut self, frame: &mut Frame, area: Rect) {
+        let theme = &self.config.theme;
+        let mut segments = self.selection_segments();
+        self.path_segments.clear();
+        let count = segments.len();
+        if count == 0 {
+            return;
+        }
+        for (_, name) in segments.iter_mut().take(count.saturating_sub(1)) {
+            if name.chars().count() > MAX_SEGMENT_WIDTH {
+                *name = name.chars().take(MAX_SEGMENT_WIDTH - 1).collect();
+                name.push('…');
+            }
+        }
+        let separator_width = Span::raw(PATH_SEPARATOR).width();
+        let names_width = |segments: &[(Vec<u64>, String)]| {
+            segments
+                .iter()
+                .map(|(_, name)| Span::raw(name.as_str()).width() + separator_width)
+                .sum::<usize>()
+        };
+        // Keep the top level and as many of the innermost levels as fit,
+        // replacing the others with an ellipsis
+        let fits = |skipped: usize| {
+            let ellipsis = if skipped > 0 { 1 + separator_width } else { 0 };
+            names_width(&segments[..1]) + ellipsis + names_width(&segments[1 + skipped..])
+                - separator_width
+                <= area.width as usize
+        };
+        let mut skipped = 0;
+        while count > 2 && skipped < count - 2 && !fits(skipped) {
+            skipped += 1;
+        }
+        if skipped > 0 {
+            segments.splice(1..1 + skipped, [(Vec::new(), "…".to_string())]);
+        }
+        let last = segments.len() - 1;
+
+        let mut spans = Vec::new();
+        let mut x = area.x;
+        for (idx, (ids, name)) in segments.into_iter().enumerate() {
+            if idx > 0 {
+                let separator = Span::styled(PATH_SEPARATOR, theme.ratio_style());
+                x = x.saturating_add(separator.width() as u16);
+                spans.push(separator);
+            }
+            let style = match idx == last {
+                true => theme.name_style().patch(theme.header_style()),
+                false if ids.is_empty() => theme.self_time_style(),
+                false => theme.name_style(),
+            };
+            let span = Span::styled(name, style);
+            let segment_width = span.width() as u16;
+            if !ids.is_empty() {
+                let segment_area = Rect::new(x, area.y, segment_width, 1).intersection(area);
+                self.path_segments.push((segment_area, ids));
+            }
+            x = x.saturating_add(segment_width);
+            spans.push(span);
+        }
+        frame.render_widget(Line::from(spans), area);
+    }
+
+    /// Select the ancestor whose segment of the selection path is at
+    /// `position`, returning whether there is any
+    fn click_path_segment(&mut self, position: Position) -> bool {
+        let Some((_, ids)) = self
+            .path_segments
+            .iter()
+            .find(|(area, _)| area.contains(position))
+        else {
+            return false;
+        };
+        self.state.select(ids.clone());
+        true
+    }
+
+    /// Go back to the previous focus level
+    fn unfocus(&mut self) {
+        if let Some(focus) = self.focus.pop() {
+            self.state = focus.state;
+            self.rebuild();
+        }
+    }
+
+    /// Leave all focus levels, without rebuilding the items
+    fn clear_focus(&mut self) {
+        if !self.focus.is_empty() {
+            self.state = self.focus.swap_remove(0).state;
+            self.focus.clear();
+        }
+    }
+
+    /// Select the call at `path` of the unfiltered tree and open all of its
+    /// ancestors
+    pub fn select_node(&mut self, path: &[usize]) {
+        if self.mode != TreeMode::Calls {
+            self.toggle_mode();
+        }
+        match &self.tree {
+            Some(tree) if !self.filter.is_empty() => {
+                let path = tree.filtered_path(&self.filter, path);
+                self.reveal(&path);
+            }
+            _ => self.reveal(path),
+        }
+    }
+
+    /// Select the node at `path` of the shown tree and open all of its
+    /// ancestors
+    fn reveal(&mut self, path: &[usize]) {
+        if !path.starts_with(self.focus_path()) {
+            self.clear_focus();
+            self.rebuild();
+        }
+        let path = &path[self.focus_prefix().len()..];
+        let mut items = self.data.as_slice();
+        let mut ids = Vec::new();
+        for &idx in path {
+            let id = idx as u64;
+            let item = match items.iter().find(|item| *item.identifier() == id) {
+                Some(item) => item,
+                None => {
+                    // The call is folded into a group
+                    let Some(group) = items.iter().find(|item| {
+                        *item.identifier() & GROUP_FLAG != 0
+                            && item
+                                .children()
+                                .iter()
+                                .any(|child| *child.identifier() == id)
+                    }) else {
+                        // The call is hidden by the threshold
+                        if items.iter().any(|item| *item.identifier() == HIDDEN_ID) {
+                            ids.push(HIDDEN_ID);
+                        }
+                        break;
+                    };
+                    let Some(item) = group
+                        .children()
+                        .iter()
+                        .find(|item| *item.identifier() == id)
+                    else {
+                        return;
+                    };
+                    ids.push(*group.identifier());
+                    item
                 }
-                KeyCode::Left => {
-                    self.state.key_left();
+            };
+            ids.push(id);
+            items = item.children();
+        }
+        for depth in 1..ids.len() {
+            self.state.open(ids[..depth].to_vec());
+        }
+        self.state.select(ids);
+    }
+
+    pub fn filter(&self) -> &SymbolFilter {
+        &self.filter
+    }
+
+    pub fn set_filter(&mut self, filter: SymbolFilter) {
+        // Paths change with the filter
+        self.clear_focus();
+        self.clear_anchor();
+        self.level_sorts.clear();
+        self.filter = filter;
+        self.filtered = None;
+        self.aggregated = None;
+        self.filtered_baseline = None;
+        self.deltas = None;
+        self.update_bookmarked();
+        self.rebuild();
+    }
+
+    pub fn search(&self) -> &str {
+        &self.search
+    }
+
+    /// Search `query` and select the first match from the selected node on
+    pub fn set_search(&mut self, query: &str) {
+        self.search = query.to_string();
+        self.rebuild();
+        let (_, next) = self.matches_around(true);
+        if let Some(path) = next.or_else(|| self.matches.first().cloned()) {
+            self.reveal(&path);
+        }
+    }
+
+    /// Select the next or previous match in the order of the rows
+    fn select_match(&mut self, forward: bool) {
+        let (previous, next) = self.matches_around(false);
+        let path = if forward {
+            next.or_else(|| self.matches.first().cloned())
+        } else {
+            previous.or_else(|| self.matches.last().cloned())
+        };
+        if let Some(path) = path {
+            self.reveal(&path);
+        }
+    }
+
+    /// Paths of the last match above the selected row and of the first one
+    /// below it, or at it with `from_selected`
+    fn matches_around(&self, from_selected: bool) -> (Option<Vec<usize>>, Option<Vec<usize>>) {
+        let selected = self.selected_path();
+        let query = self.search.to_lowercase();
+        let mut passed = false;
+        let (mut previous, mut next) = (None, None);
+        self.visit_rows(&mut |path, node| {
+            let matched = node.is_some_and(|node| matches_query(node, &query));
+            if path == selected {
+                passed = true;
+                if !from_selected {
+                    return;
                 }
-                KeyCode::Right => {
-                    self.state.key_right();
+            }
+            if !matched || next.is_some() {
+                return;
+            }
+            if passed {
+                next = Some(path.to_vec());
+            } else {
+                previous = Some(path.to_vec());
+            }
+        });
+        (previous, next)
+    }
+
+    /// Collect the paths of the shown nodes matching the search, in the
+    /// order of the rows
+    fn find_matches(&mut self) {
+        let mut matches = Vec::new();
+        let query = self.search.to_lowercase();
+        if !query.is_empty() {
+            self.visit_rows(&mut |path, node| {
+                if node.is_some_and(|node| matches_query(node, &query)) {
+                    matches.push(path.to_vec());
+                }
+            });
+        }
+        self.matches = matches;
+    }
+
+    /// Call `visit` with the path and node of every row in the order they
+    /// are shown, whether folded or not. The calls below the threshold
+    /// follow the hidden row standing for them, which has no node.
+    fn visit_rows(&self, visit: &mut impl FnMut(&[usize], Option<&FtraceNode>)) {
+        fn visit_nodes<'a>(
+            nodes: impl Iterator<Item = &'a FtraceNode>,
+            path: &mut Vec<usize>,
+            visit: &mut impl FnMut(&[usize], Option<&FtraceNode>),
+        ) {
+            for (idx, node) in nodes.enumerate() {
+                path.push(idx);
+                visit(path, Some(node));
+                visit_nodes(node.children(), path, visit);
+                path.pop();
+            }
+        }
+
+        fn visit_items(
+            tree: &FtraceTree,
+            items: &[TreeItem<'static, u64>],
+            path: &mut Vec<usize>,
+            visit: &mut impl FnMut(&[usize], Option<&FtraceNode>),
+        ) {
+            for item in items {
+                match *item.identifier() {
+                    HIDDEN_ID => {
+                        let shown: HashSet<_> = items
+                            .iter()
+                            .flat_map(|item| std::iter::once(item).chain(item.children()))
+                            .map(|item| *item.identifier())
+                            .filter(|&id| id & GROUP_FLAG == 0)
+                            .collect();
+                        path.push(usize::MAX);
+                        visit(path, None);
+                        path.pop();
+                        let siblings: Vec<_> = match tree.node_at(path) {
+                            Some(node) => node.children().collect(),
+                            None => tree.children().collect(),
+                        };
+                        for (idx, node) in siblings.into_iter().enumerate() {
+                            if shown.contains(&(idx as u64)) {
+                                continue;
+                            }
+                            path.push(idx);
+                            visit(path, Some(node));
+                            visit_nodes(node.children(), path, visit);
+                            path.pop();
+                        }
+                    }
+                    id if id & GROUP_FLAG != 0 => {
+                        visit_items(tree, item.children(), path, visit);
+                    }
+                    id => {
+                        path.push(id as usize);
+                        visit(path, tree.node_at(path));
+                        visit_items(tree, item.children(), path, visit);
+                        path.pop();
+                    }
                 }
-                KeyCode::Home => {
-                    self.state.select_first();
+            }
+        }
+
+        if let Some(tree) = self.shown_tree() {
+            let mut path = self.focus_prefix().to_vec();
+            visit_items(tree, &self.data, &mut path, visit);
+        }
+    }
+
+    /// Path of the caller of the selected node and its siblings
+    fn selected_level(&self) -> Vec<usize> {
+        let mut path = self.selected_path();
+        path.pop();
+        path
+    }
+
+    /// Sort the siblings of the selected node by the next order
+    fn cycle_level_sort(&mut self) {
+        let level = self.selected_level();
+        let sort = self.level_sorts.get(&level).copied().unwrap_or(self.sort);
+        self.level_sorts.insert(level, sort.next());
+        self.rebuild();
+    }
+
+    /// Sort the siblings on all levels by the next order
+    fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+        self.level_sorts.clear();
+        self.rebuild();
+    }
+
+    /// Description of the order of the siblings of the selected node
+    pub fn sort_status(&self) -> String {
+        match self.level_sorts.get(&self.selected_level()) {
+            Some(sort) if *sort != self.sort => {
+                format!("Sorted by {} (this level by {})", self.sort, sort)
+            }
+            _ => format!("Sorted by {}", self.sort),
+        }
+    }
+
+    pub fn columns(&self) -> &[TreeColumn] {
+        &self.columns
+    }
+
+    pub fn set_columns(&mut self, columns: Vec<TreeColumn>) {
+        self.columns = columns;
+        // The number of calls moves into the names without its column
+        self.rebuild();
+    }
+
+    /// Total time of the shown root: the focused node, or the whole trace
+    fn root_time(&self) -> Duration {
+        self.shown_tree()
+            .map(|tree| match tree.node_at(self.focus_path()) {
+                Some(node) => node.time().unwrap_or_default(),
+                None => tree.total_time(),
+            })
+            .unwrap_or_default()
+    }
+
+    /// Item at the identifier path `ids`
+    fn item_at(&self, ids: &[u64]) -> Option<&TreeItem<'static, u64>> {
+        let (first, rest) = ids.split_first()?;
+        let find = |items: &'_ [TreeItem<'static, u64>], id: u64| {
+            items.iter().position(|item| *item.identifier() == id)
+        };
+        let mut item = &self.data[find(&self.data, *first)?];
+        for id in rest {
+            item = &item.children()[find(item.children(), *id)?];
+        }
+        Some(item)
+    }
+
+    /// Figures of the row at the identifier path `ids`
+    fn row_figures(&self, ids: &[u64]) -> Option<RowFigures> {
+        let tree = self.shown_tree()?;
+        let (&last, ancestors) = ids.split_last()?;
+        let mut path = self.focus_prefix().to_vec();
+        path.extend(
+            ancestors
+                .iter()
+                .filter(|&&id| id & GROUP_FLAG == 0)
+                .map(|&id| id as usize),
+        );
+        let root_time = self.root_time();
+        let parent = tree.node_at(&path);
+        let siblings: Vec<_> = match parent {
+            Some(node) => node.children().collect(),
+            None => tree.children().collect(),
+        };
+        let upper_time = match parent {
+            // The focused node is relative to itself
+            _ if ancestors.is_empty() && !self.focus.is_empty() => root_time,
+            Some(node) => node.time().unwrap_or_default(),
+            None => tree.total_time(),
+        };
+        let base_time = match self.percent_base {
+            PercentBase::Parent => Some(upper_time),
+            PercentBase::Root => Some(root_time),
+            PercentBase::Anchor => {
+                // Groups and hidden calls are only within the anchor if
+                // their caller is
+                let mut own_path = path.clone();
+                if last & GROUP_FLAG == 0 {
+                    own_path.push(last as usize);
                 }
-                KeyCode::End => {
-                    self.state.select_last();
+                self.anchor
+                    .as_ref()
+                    .filter(|anchor| own_path.starts_with(anchor))
+                    .and_then(|anchor| tree.node_at(anchor))
+                    .map(|anchor| anchor.time().unwrap_or_default())
+            }
+        };
+        let sum = |nodes: &[&FtraceNode], addr: Option<u64>| RowFigures {
+            time: nodes
+                .iter()
+                .map(|node| node.time().unwrap_or_default())
+                .sum(),
+            self_time: addr.map(|_| {
+                nodes
+                    .iter()
+                    .map(|node| node.self_time().unwrap_or_default())
+                    .sum()
+            }),
+            base_time,
+            root_time,
+            calls: nodes.iter().map(|node| node.calls()).sum(),
+            addr,
+        };
+        let figures = match last {
+            HIDDEN_ID => {
+                let threshold = self.threshold?;
+                let hidden: Vec<_> = siblings
+                    .into_iter()
+                    .filter(|node| {
+                        threshold.hides(node.time().unwrap_or_default(), upper_time, root_time)
+                    })
+                    .collect();
+                sum(&hidden, None)
+            }
+            id if id & GROUP_FLAG != 0 => {
+                let run = self
+                    .item_at(ids)?
+                    .children()
+                    .iter()
+                    .map(|item| siblings.get(*item.identifier() as usize).copied())
+                    .collect::<Option<Vec<_>>>()?;
+                sum(&run, run.first().map(|node| node.func()))
+            }
+            id => {
+                let node = *siblings.get(id as usize)?;
+                sum(&[node], Some(node.func()))
+            }
+        };
+        Some(figures)
+    }
+
+    /// Make the percentages relative to the next base, anchoring them at the
+    /// selected node when switching to [`PercentBase::Anchor`]
+    fn cycle_percent_base(&mut self) {
+        self.percent_base = self.percent_base.next();
+        if self.percent_base == PercentBase::Anchor {
+            if self.selected_node().is_some() {
+                self.anchor = Some(self.selected_path());
+            } else {
+                self.percent_base = self.percent_base.next();
+            }
+        }
+    }
+
+    /// Forget the anchor, whose path is no longer valid
+    fn clear_anchor(&mut self) {
+        self.anchor = None;
+        if self.percent_base == PercentBase::Anchor {
+            self.percent_base = PercentBase::Parent;
+        }
+    }
+
+    pub fn threshold(&self) -> Option<Threshold> {
+        self.threshold
+    }
+
+    pub fn set_threshold(&mut self, threshold: Option<Threshold>) {
+        self.threshold = threshold;
+        self.rebuild();
+    }
+
+    /// Starting at the selected node, follow the callee with the largest
+    /// `key` down to a leaf and select it
+    fn expand_hot_path(&mut self, key: impl Fn(&FtraceNode) -> Duration) {
+        let Some(tree) = self.shown_tree() else {
+            return;
+        };
+        let mut path = self.selected_path();
+        let mut children: Vec<_> = match tree.node_at(&path) {
+            Some(node) => node.children().collect(),
+            // Without a selection, start from the top level
+            None if path.is_empty() => tree.children().collect(),
+            None => return,
+        };
+        while let Some((idx, hottest)) = children
+            .iter()
+            .enumerate()
+            .max_by_key(|(idx, node)| (key(node), std::cmp::Reverse(*idx)))
+        {
+            path.push(idx);
+            children = hottest.children().collect();
+        }
+        self.reveal(&path);
+    }
+
+    fn toggle_mode(&mut self) {
+        self.clear_focus();
+        self.clear_anchor();
+        self.level_sorts.clear();
+        self.mode = match self.mode {
+            TreeMode::Calls => TreeMode::Aggregated,
+            TreeMode::Aggregated => TreeMode::Calls,
+        };
+        std::mem::swap(&mut self.state, &mut self.other_state);
+        self.deltas = None;
+        self.update_bookmarked();
+        self.rebuild();
+    }
+
+    /// Show `tree`, a refresh of the shown one once symbols are resolved.
+    ///
+    /// Identifiers are the indices of the calls among their siblings, so
+    /// paths of the unfiltered calls stay valid. Resolving changes the names
+    /// though, and so which calls the filter keeps and which ones are
+    /// merged: paths of the filtered and aggregated trees are forgotten, only
+    /// the selected call is selected again.
+    fn update_tree(&mut self, tree: Arc<FtraceTree>) {
+        let refresh = self.tree.is_some();
+        let selected_call = self.selected_call();
+        self.tree = Some(tree);
+        self.filtered = None;
+        self.aggregated = None;
+        self.deltas = None;
+        if refresh {
+            let calls_kept = self.filter.is_empty();
+            match self.mode {
+                TreeMode::Calls => {
+                    self.other_state = TreeState::default();
+                    if !calls_kept {
+                        self.forget_paths();
+                    }
                 }
-                KeyCode::Enter => {
-                    self.state.toggle_selected();
+                TreeMode::Aggregated => {
+                    self.forget_paths();
+                    if !calls_kept {
+                        self.other_state = TreeState::default();
+                    }
                 }
-                KeyCode::PageUp => {
-                    self.state.scroll_up(10);
+            }
+        }
+        self.update_bookmarked();
+        self.rebuild();
+        if refresh
+            && !self.filter.is_empty()
+            && let Some(path) = selected_call
+        {
+            self.select_node(&path);
+        }
+    }
+
+    /// Forget everything referring to paths of the shown tree
+    fn forget_paths(&mut self) {
+        self.focus.clear();
+        self.clear_anchor();
+        self.level_sorts.clear();
+        self.state = TreeState::default();
+    }
+
+    fn rebuild(&mut self) {
+        if !self.filter.is_empty()
+            && self.filtered.is_none()
+            && let Some(tree) = &self.tree
+        {
+            self.filtered = Some(Arc::new(tree.filter(&self.filter)));
+        }
+        if self.mode == TreeMode::Aggregated
+            && self.aggregated.is_none()
+            && let Some(tree) = self.calls_tree()
+        {
+            self.aggregated = Some(Arc::new(tree.aggregate()));
+        }
+        // Compare with the same calls of the baseline
+        if !self.filter.is_empty()
+            && self.filtered_baseline.is_none()
+            && let Some(baseline) = &self.baseline
+        {
+            self.filtered_baseline = Some(Arc::new(baseline.filter(&self.filter)));
+        }
+        let baseline = if self.filter.is_empty() {
+            self.baseline.as_deref()
+        } else {
+            self.filtered_baseline.as_deref()
+        };
+        let by_call_path = self.mode == TreeMode::Aggregated;
+        if self.deltas.is_none() {
+            self.deltas = match (baseline, self.shown_tree()) {
+                (Some(baseline), Some(tree)) if by_call_path => Some(diff_by_key(
+                    call_path_figures(baseline),
+                    call_path_figures(tree),
+                )),
+                (Some(baseline), Some(tree)) => Some(diff_by_key(
+                    function_figures(baseline),
+                    function_figures(tree),
+                )),
+                _ => None,
+            };
+        }
+        let query = self.search.to_lowercase();
+        let builder = ItemBuilder {
+            theme: &self.config.theme,
+            search: &query,
+            sort: self.sort,
+            sorts: &self.level_sorts,
+            deltas: self.deltas.as_ref(),
+            by_call_path,
+            threshold: self.threshold,
+            root_time: self.root_time(),
+            show_calls: !self.columns.contains(&TreeColumn::Calls),
+            bookmarked: &self.bookmarked,
+        };
+        self.data = match self.shown_tree() {
+            Some(tree) => match tree.node_at(self.focus_path()) {
+                Some(node) => {
+                    let parent_path = call_path_at(tree, self.focus_prefix());
+                    vec![builder.build_item(self.focus_path(), node, &parent_path).0]
                 }
-                KeyCode::PageDown => {
-                    self.state.scroll_down(10);
+                None => {
+                    builder
+                        .build_children(tree.children(), tree.total_time(), "", &[])
+                        .0
                 }
-                _ => (),
             },
+            None => Vec::new(),
+        };
+        self.find_matches();
+    }
+}
+
+impl Component<AppMsg> for TraceTreeComponent {
+    fn handle(&mut self, event: Event<AppMsg>) {
+        match event {
+            Event::Key(key_event) if key_event.kind != KeyEventKind::Release => {
+                match self.config.keymap.action(&key_event) {
+                    Some(Action::Up) => {
+                        self.state.key_up();
+                    }
+                    Some(Action::Down) => {
+                        self.state.key_down();
+                    }
+                    Some(Action::Left) => {
+                        self.state.key_left();
+                    }
+                    Some(Action::Right) => {
+                        self.state.key_right();
+                    }
+                    Some(Action::First) => {
+                        self.state.select_first();
+                    }
+                    Some(Action::Last) => {
+                        self.state.select_last();
+                    }
+                    Some(Action::Toggle) => {
+                        self.state.toggle_selected();
+                    }
+                    Some(Action::PageUp) => {
+                        self.state.scroll_up(10);
+                    }
+                    Some(Action::PageDown) => {
+                        self.state.scroll_down(10);
+                    }
+                    Some(Action::ToggleAggregate) => {
+                        self.toggle_mode();
+                    }
+                    Some(Action::ExpandHotPath) => {
+                        self.expand_hot_path(|node| node.time().unwrap_or_default());
+                    }
+                    Some(Action::ExpandHotSelfPath) => {
+                        self.expand_hot_path(|node| node.self_time().unwrap_or_default());
+                    }
+                    Some(Action::NextMatch) => {
+                        self.select_match(true);
+                    }
+                    Some(Action::PreviousMatch) => {
+                        self.select_match(false);
+                    }
+                    Some(Action::SortColumn) => {
+                        self.cycle_level_sort();
+                    }
+                    Some(Action::SortAll) => {
+                        self.cycle_sort();
+                    }
+                    Some(Action::PercentBase) => {
+                        self.cycle_percent_base();
+                    }
+                    Some(Action::Focus) => {
+                        self.focus_selected();
+                    }
+                    Some(Action::Back) => {
+                        self.unfocus();
+                    }
+                    Some(Action::RaiseThreshold) => {
+                        let threshold = match self.threshold {
+                            Some(threshold) => threshold.scaled(2.0),
+                            None => DEFAULT_THRESHOLD,
+                        };
+                        self.set_threshold(Some(threshold));
+                    }
+                    Some(Action::LowerThreshold) => {
+                        self.set_threshold(self.threshold.map(|threshold| threshold.scaled(0.5)));
+                    }
+                    _ => (),
+                }
+            }
             Event::Mouse(mouse_event) => match mouse_event.kind {
                 MouseEventKind::Down(MouseButton::Left) => {
-                    self.state
-                        .click_at(Position::new(mouse_event.column, mouse_event.row));
+                    let position = Position::new(mouse_event.column, mouse_event.row);
+                    if !self.click_path_segment(position) {
+                        self.state.click_at(position);
+                    }
                 }
                 MouseEventKind::ScrollDown => {
                     self.state.scroll_down(3);
@@ -104,134 +1378,676 @@ impl Component<AppMsg> for TraceTreeComponent {
             Event::Message(AppMsg::SetFtraceTitle(title)) => {
                 self.title = format!(" {} ", title);
             }
-            Event::Message(AppMsg::UpdateTree(tree_data)) => {
-                self.data = tree_data;
-                self.state = TreeState::default();
+            Event::Message(AppMsg::UpdateBaseline(baseline)) => {
+                self.baseline = Some(baseline);
+                self.filtered_baseline = None;
+                self.deltas = None;
+                self.rebuild();
             }
+            Event::Message(AppMsg::UpdateTree(tree)) => self.update_tree(tree),
             _ => (),
         }
     }
 
     fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let mut title = match self.mode {
+            TreeMode::Calls => self.title.clone(),
+            TreeMode::Aggregated => format!("{}[aggregated] ", self.title),
+        };
+        if !self.filter.is_empty() {
+            title.push_str("[filtered] ");
+        }
+        if let Some(threshold) = self.threshold {
+            title.push_str(&format!("[≥ {}] ", threshold));
+        }
+        if self.percent_base == PercentBase::Anchor
+            && let Some(anchor) = self
+                .anchor
+                .as_ref()
+                .and_then(|anchor| self.shown_tree()?.node_at(anchor))
+        {
+            title.push_str(&format!("[% of {}] ", anchor.name()));
+        }
+        if !self.search.is_empty() {
+            let selected = self.selected_path();
+            match self.matches.iter().position(|path| *path == selected) {
+                _ if self.matches.is_empty() => {
+                    title.push_str(&format!("[/{}: no match] ", self.search));
+                }
+                Some(idx) => title.push_str(&format!(
+                    "[/{}: {}/{}] ",
+                    self.search,
+                    idx + 1,
+                    self.matches.len()
+                )),
+                None => title.push_str(&format!(
+                    "[/{}: {} matches] ",
+                    self.search,
+                    self.matches.len()
+                )),
+            }
+        }
         let block = Block::bordered()
-            .title(self.title.as_str())
+            .title(title)
             .title_alignment(Alignment::Center)
             .border_type(BorderType::Rounded);
+        let mut tree_area = block.inner(area);
+        frame.render_widget(block, area);
+        if !self.focus.is_empty() {
+            let [breadcrumb_area, rest] =
+                Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(tree_area);
+            frame.render_widget(Paragraph::new(self.breadcrumb()), breadcrumb_area);
+            tree_area = rest;
+        }
+        let [path_area, rest] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(tree_area);
+        tree_area = rest;
+        let theme = &self.config.theme;
+        let columns_width = self.columns.iter().map(|column| column.width() + 1).sum();
+        let [header_area, rows_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(tree_area);
+        let split = |area| {
+            Layout::horizontal([Constraint::Fill(1), Constraint::Length(columns_width)])
+                .areas::<2>(area)
+        };
+        let [name_header_area, columns_header_area] = split(header_area);
+        let [name_area, columns_area] = split(rows_area);
+        let titles = self.columns.iter().map(|column| {
+            let width = column.width() as usize;
+            Span::raw(format!(" {:>width$}", column.title(self.percent_base)))
+        });
+        frame.render_widget(
+            Line::from("Function").style(theme.header_style()),
+            name_header_area,
+        );
+        frame.render_widget(
+            Line::from_iter(titles).style(theme.header_style()),
+            columns_header_area,
+        );
+
         let widget = Tree::new(&self.data)
             .unwrap()
-            .block(block)
-            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
-        frame.render_stateful_widget(widget, area, &mut self.state);
+            .highlight_style(theme.highlight_style());
+        frame.render_stateful_widget(widget.clone(), name_area, &mut self.state);
+        // Scrolling is bounded by the items of the last render, so a restored
+        // position is applied after rendering them once
+        if !self.data.is_empty()
+            && let Some(offset) = self.restored_offset.take()
+        {
+            self.state.scroll_up(usize::MAX);
+            self.state.scroll_down(offset);
+            frame.render_widget(Clear, name_area);
+            frame.render_stateful_widget(widget, name_area, &mut self.state);
+        }
+
+        // The tree widget only draws the names, fill in the columns of the
+        // rows it rendered, one line per item
+        let flattened = self.state.flatten(&self.data);
+        let rows = flattened.iter().skip(self.state.get_offset());
+        for (row_area, row) in columns_area.rows().zip(rows) {
+            let ids = row.identifier.as_slice();
+            let Some(figures) = self.row_figures(ids) else {
+                continue;
+            };
+            let cells = self.columns.iter().flat_map(|column| {
+                let cell = column.cell(&figures, theme);
+                let width = column.width() as usize;
+                [
+                    Span::raw(" "),
+                    Span::styled(format!("{:>width$}", cell.content), cell.style),
+                ]
+            });
+            if ids == self.state.selected() {
+                frame
+                    .buffer_mut()
+                    .set_style(row_area, theme.highlight_style());
+            }
+            frame.render_widget(Line::from_iter(cells), row_area);
+        }
+        self.render_selection_path(frame, path_area);
     }
 }
 
-fn build_ftrace_ui_tree(
-    identifier: u64,
-    node: &FtraceNode,
-    upper_time: Duration,
-) -> TreeItem<'static, u64> {
-    let mut children_tree_items = Vec::new();
-    let time = node.time().unwrap_or_default();
-    for (child_id, children) in node.children().enumerate() {
-        children_tree_items.push(build_ftrace_ui_tree(child_id as u64, children, time));
+/// Identifiers of an item as saved in a [`TreeSession`]
+fn format_ids(ids: &[u64]) -> String {
+    let ids: Vec<_> = ids
+        .iter()
+        .map(|&id| match id {
+            HIDDEN_ID => "hidden".to_string(),
+            id if id & GROUP_FLAG != 0 => format!("g{}", id & !GROUP_FLAG),
+            id => id.to_string(),
+        })
+        .collect();
+    ids.join("/")
+}
+
+/// Identifiers of an item saved by [`format_ids`]
+fn parse_ids(s: &str) -> Option<Vec<u64>> {
+    if s.is_empty() {
+        return Some(Vec::new());
     }
-    let self_time = node.self_time().unwrap_or_default();
+    s.split('/')
+        .map(|id| match id {
+            "hidden" => Some(HIDDEN_ID),
+            id => match id.strip_prefix('g') {
+                Some(idx) => idx.parse::<u64>().ok().map(|idx| idx | GROUP_FLAG),
+                None => id.parse().ok(),
+            },
+        })
+        .collect()
+}
+
+/// Relative change from the baseline at which a node is highlighted
+const CHANGE_THRESHOLD: f64 = 5.0;
 
-    let text = TraceLine {
-        addr: node.func(),
-        symbol: node.symbol().map(|s| s.to_owned()),
-        time,
-        self_time,
-        upper_time,
-    };
-    TreeItem::new(identifier, text, children_tree_items).unwrap()
+/// Builds the items of a tree
+struct ItemBuilder<'a> {
+    theme: &'a Theme,
+    /// Lowercase text searched, empty if there is no search
+    search: &'a str,
+    sort: TreeSort,
+    /// Sort orders of single levels, keyed by the path of their caller
+    sorts: &'a HashMap<Vec<usize>, TreeSort>,
+    /// Changes against the baseline, keyed by function name, or by call path
+    /// if `by_call_path` is set
+    deltas: Option<&'a HashMap<String, Delta>>,
+    by_call_path: bool,
+    threshold: Option<Threshold>,
+    /// Total time of the trace, for thresholds relative to it
+    root_time: Duration,
+    /// Whether the names show the number of calls, for lack of its column
+    show_calls: bool,
+    /// Paths of the bookmarked nodes
+    bookmarked: &'a HashSet<Vec<usize>>,
 }
 
-const NAME_SPAN_STYLE: Style = Style::new().fg(Color::White);
-const TIME_SPAN_STYLE: Style = Style::new().fg(Color::Yellow);
-const SELF_TIME_SPAN_STYLE: Style = Style::new().fg(Color::DarkGray);
-const RATIO_SPAN_STYLE: Style = Style::new().fg(Color::Blue);
+impl ItemBuilder<'_> {
+    fn change(&self, key: &str) -> Option<Change> {
+        self.deltas?.get(key).map(Delta::total_change)
+    }
+
+    /// Build the item of a node, along with a signature of its function and
+    /// the structure of its subtree
+    fn build_item(
+        &self,
+        path: &[usize],
+        node: &FtraceNode,
+        parent_path: &str,
+    ) -> (TreeItem<'static, u64>, u64, Option<Change>) {
+        let identifier = path.last().copied().unwrap_or_default() as u64;
+        let key = if self.by_call_path {
+            call_path(parent_path, &node.function())
+        } else {
+            node.function()
+        };
+        let time = node.time().unwrap_or_default();
+        let (children_tree_items, children_signature) =
+            self.build_children(node.children(), time, &key, path);
+
+        let change = self.change(&key);
+        let text = TraceLine {
+            addr: node.func(),
+            symbol: node.symbol().map(|s| s.to_string()),
+            calls: self.show_calls.then_some(node.calls()),
+            range: None,
+            change,
+            matched: matches_query(node, self.search),
+            bookmarked: self.bookmarked.contains(path),
+        };
+        let item =
+            TreeItem::new(identifier, text.into_text(self.theme), children_tree_items).unwrap();
+
+        let mut hasher = DefaultHasher::new();
+        node.function().hash(&mut hasher);
+        children_signature.hash(&mut hasher);
+        (item, hasher.finish(), change)
+    }
+
+    /// Build the items of sibling nodes in the sort order of their level,
+    /// folding runs of identical consecutive calls into a group item and
+    /// gathering the calls below the threshold into one item
+    fn build_children<'a>(
+        &self,
+        nodes: impl Iterator<Item = &'a FtraceNode>,
+        upper_time: Duration,
+        parent_path: &str,
+        parent: &[usize],
+    ) -> (Vec<TreeItem<'static, u64>>, u64) {
+        let mut hasher = DefaultHasher::new();
+        let mut calls = Vec::new();
+        let (mut hidden_calls, mut hidden_time) = (0, Duration::ZERO);
+        for (child_id, node) in nodes.enumerate() {
+            let time = node.time().unwrap_or_default();
+            if let Some(threshold) = &self.threshold
+                && threshold.hides(time, upper_time, self.root_time)
+            {
+                hidden_calls += node.calls();
+                hidden_time += time;
+                // Still part of the signature, so only identical subtrees fold
+                node.function().hash(&mut hasher);
+                continue;
+            }
+            let path: Vec<_> = parent.iter().copied().chain([child_id]).collect();
+            let (item, signature, change) = self.build_item(&path, node, parent_path);
+            signature.hash(&mut hasher);
+            calls.push((child_id, item, signature, node, change));
+        }
+
+        // Fold in call order, so that only calls made one after the other
+        // share a row, then sort the rows
+        let mut rows = Vec::new();
+        let mut calls = calls.into_iter().peekable();
+        while let Some((first_id, item, signature, node, change)) = calls.next() {
+            let (mut items, mut nodes) = (vec![item], vec![node]);
+            while let Some((_, item, _, node, _)) =
+                calls.next_if(|(_, _, next_signature, _, _)| *next_signature == signature)
+            {
+                items.push(item);
+                nodes.push(node);
+            }
+            rows.push((first_id, items, nodes, change));
+        }
+        let sort = self.sorts.get(parent).copied().unwrap_or(self.sort);
+        sort.sort(&mut rows, |(_, _, nodes, _)| nodes);
+
+        let mut items = Vec::new();
+        for (first_id, mut run, nodes, change) in rows {
+            if run.len() == 1 {
+                items.push(run.pop().unwrap());
+            } else {
+                items.push(self.build_group(first_id as u64, run, &nodes, change));
+            }
+        }
+        if hidden_calls > 0 {
+            let text = hidden_text(hidden_calls, hidden_time, self.theme);
+            items.push(TreeItem::new_leaf(HIDDEN_ID, text));
+        }
+        (items, hasher.finish())
+    }
+
+    fn build_group(
+        &self,
+        first_id: u64,
+        run: Vec<TreeItem<'static, u64>>,
+        nodes: &[&FtraceNode],
+        change: Option<Change>,
+    ) -> TreeItem<'static, u64> {
+        let first = nodes[0];
+        let times: Vec<_> = nodes
+            .iter()
+            .map(|node| node.time().unwrap_or_default())
+            .collect();
+        let text = TraceLine {
+            addr: first.func(),
+            symbol: first.symbol().map(|s| s.to_string()),
+            calls: self
+                .show_calls
+                .then(|| nodes.iter().map(|node| node.calls()).sum()),
+            range: Some((
+                times.iter().min().copied().unwrap_or_default(),
+                times.iter().max().copied().unwrap_or_default(),
+            )),
+            change,
+            matched: matches_query(first, self.search),
+            bookmarked: false,
+        };
+        TreeItem::new(GROUP_FLAG | first_id, text.into_text(self.theme), run).unwrap()
+    }
+}
 
+/// Name column of a line of the tree
 #[derive(Clone, Debug)]
 pub struct TraceLine {
     pub addr: u64,
     pub symbol: Option<String>,
-    pub time: Duration,
-    pub self_time: Duration,
-    pub upper_time: Duration,
+    /// Number of calls merged into the line, if not shown in a column
+    pub calls: Option<usize>,
+    /// Shortest and longest call of a group
+    pub range: Option<(Duration, Duration)>,
+    /// Change of the total time of the function against the baseline
+    pub change: Option<Change>,
+    /// Whether the function matches the search
+    pub matched: bool,
+    pub bookmarked: bool,
 }
 
-impl From<TraceLine> for Text<'static> {
-    fn from(line: TraceLine) -> Self {
+impl TraceLine {
+    pub fn into_text(self, theme: &Theme) -> Text<'static> {
+        let line = self;
+        let name_style = match line.matched {
+            true => theme.search_match_style(),
+            false => theme.name_style(),
+        };
         let name_span = if let Some(symbol) = line.symbol {
-            Span::styled(format!("{}()", symbol), NAME_SPAN_STYLE)
+            Span::styled(format!("{}()", symbol), name_style)
         } else {
-            Span::styled(format!("0x{:016x}()", line.addr), NAME_SPAN_STYLE)
+            Span::styled(format!("0x{:016x}()", line.addr), name_style)
+        };
+        let calls_span = match line.calls {
+            None | Some(1) => Span::raw(""),
+            Some(calls) => Span::styled(format!(" ×{}", calls), theme.ratio_style()),
+        };
+        let range_span = match line.range {
+            Some((min, max)) => Span::styled(
+                format!(
+                    " (min: {}, max: {})",
+                    format_duration(min).trim_start(),
+                    format_duration(max).trim_start()
+                ),
+                theme.self_time_style(),
+            ),
+            None => Span::raw(""),
+        };
+        let change_span = match line.change {
+            Some(change) => {
+                let style = match change {
+                    Change::Added => theme.regression_style(),
+                    Change::Percent(p) if p >= CHANGE_THRESHOLD => theme.regression_style(),
+                    Change::Percent(p) if p <= -CHANGE_THRESHOLD => theme.improvement_style(),
+                    _ => theme.self_time_style(),
+                };
+                Span::styled(format!(" Δ{}", format_change(change)), style)
+            }
+            None => Span::raw(""),
+        };
+
+        let bookmark_span = match line.bookmarked {
+            true => Span::styled("⚑ ", theme.bookmark_style()),
+            false => Span::raw(""),
         };
-        let time_span = Span::styled(format_duration(line.time), TIME_SPAN_STYLE);
-        let self_time_span = Span::styled(
-            format!("(self: {})", format_duration(line.self_time)),
-            SELF_TIME_SPAN_STYLE,
-        );
-        let time_ratio = line.time.as_nanos() as f64 / line.upper_time.as_nanos() as f64 * 100.0;
-        let ratio_span = Span::styled(format!("[{:.2}%]", time_ratio), RATIO_SPAN_STYLE);
 
         Line::from_iter([
+            bookmark_span,
             name_span,
-            Span::raw(" "),
-            time_span,
-            Span::raw(" "),
-            self_time_span,
-            Span::raw(" "),
-            ratio_span,
+            calls_span,
+            range_span,
+            change_span,
         ])
         .into()
     }
 }
 
-fn format_duration(duration: Duration) -> String {
-    if duration.as_secs() >= 1000 {
-        format!("{:7} s ", duration.as_secs_f64())
-    } else if duration.as_secs() > 0 {
+/// Name column of the item gathering the calls hidden by the threshold
+fn hidden_text(calls: usize, time: Duration, theme: &Theme) -> Text<'static> {
+    Span::styled(
         format!(
-            "{:3}.{:03} s ",
-            duration.as_secs(),
-            duration.subsec_millis()
-        )
-    } else if duration.as_millis() > 0 {
-        format!(
-            "{:3}.{:03} ms",
-            duration.as_millis(),
-            duration.subsec_micros() % 1000
-        )
-    } else if duration.as_micros() > 0 {
-        format!(
-            "{:3}.{:03} µs",
-            duration.as_micros(),
-            duration.subsec_nanos() % 1000
-        )
-    } else {
-        format!("{:7} ns", duration.as_nanos())
+            "({} hidden calls, {})",
+            calls,
+            format_duration(time).trim_start()
+        ),
+        theme.self_time_style(),
+    )
+    .into()
+}
+
+/// Whether the name or the address of `node` contains the lowercase `query`
+fn matches_query(node: &FtraceNode, query: &str) -> bool {
+    if query.is_empty() {
+        return false;
     }
+    let hex = query.strip_prefix("0x").unwrap_or(query);
+    node.name().to_lowercase().contains(query)
+        || (!hex.is_empty()
+            && hex.chars().all(|c| c.is_ascii_hexdigit())
+            && format!("{:x}", node.func()).contains(hex))
+}
+
+/// Call path of the node at `path`
+fn call_path_at(tree: &FtraceTree, path: &[usize]) -> String {
+    (1..=path.len())
+        .filter_map(|depth| tree.node_at(&path[..depth]))
+        .fold(String::new(), |parent, node| {
+            call_path(&parent, &node.function())
+        })
+}
+
+fn diff_by_key(
+    base: HashMap<String, Figures>,
+    new: HashMap<String, Figures>,
+) -> HashMap<String, Delta> {
+    diff(base, new)
+        .into_iter()
+        .map(|delta| (delta.key.clone(), delta))
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::time::Duration;
+    use crate::{ftrace::test_util::node, symbol::resolver::Symbol};
+    use ratatui::{Terminal, backend::TestBackend};
+
+    /// A call of 0x10 to 0x20, twice to 0x30 calling 0x40 and to 0x50, which
+    /// only takes 1 ns
+    fn tree() -> FtraceTree {
+        FtraceTree::new(
+            String::new(),
+            vec![node(
+                0x10,
+                1400,
+                vec![
+                    node(0x20, 100, vec![]),
+                    node(0x30, 600, vec![node(0x40, 50, vec![])]),
+                    node(0x30, 600, vec![node(0x40, 50, vec![])]),
+                    node(0x50, 1, vec![]),
+                ],
+            )],
+        )
+    }
+
+    fn component(threshold: Option<Threshold>) -> TraceTreeComponent {
+        let mut component = TraceTreeComponent::new(Arc::new(Config::default()));
+        component.threshold = threshold;
+        component.update_tree(Arc::new(tree()));
+        component
+    }
 
     #[test]
-    fn test_format_duration() {
-        assert_eq!(format_duration(Duration::from_nanos(123)), "    123 ns");
-        assert_eq!(format_duration(Duration::from_nanos(241_452)), "241.452 µs");
-        assert_eq!(
-            format_duration(Duration::from_nanos(343_234_431)),
-            "343.234 ms"
+    fn test_ids_round_trip() {
+        let ids = [0, GROUP_FLAG | 3, 5, HIDDEN_ID];
+        assert_eq!(format_ids(&ids), "0/g3/5/hidden");
+        assert_eq!(parse_ids(&format_ids(&ids)), Some(ids.to_vec()));
+        assert_eq!(parse_ids(""), Some(Vec::new()));
+        assert_eq!(parse_ids("0/gx"), None);
+    }
+
+    #[test]
+    fn test_column_from_str() {
+        assert_eq!("Self".parse(), Ok(TreeColumn::SelfTime));
+        assert_eq!("addr".parse(), Ok(TreeColumn::Address));
+        assert_eq!("root".parse(), Ok(TreeColumn::OfRoot));
+        assert!("time".parse::<TreeColumn>().is_err());
+    }
+
+    #[test]
+    fn test_matches_query() {
+        let mut node = node(0x4002_10cb, 100, vec![]);
+        assert!(matches_query(&node, "10cb"));
+        assert!(matches_query(&node, "0x4002"));
+        assert!(!matches_query(&node, "0xff"));
+        assert!(!matches_query(&node, ""));
+        node.set_symbol(Arc::new(Symbol {
+            name: "invoke".to_string(),
+            addr: 0x1000,
+            size: 0x100,
+            offset: 0,
+            location: None,
+        }));
+        assert!(matches_query(&node, "voke"));
+        assert!(matches_query(&node, "10cb"));
+        assert!(!matches_query(&node, "main"));
+    }
+
+    #[test]
+    fn test_sort() {
+        let tree = tree();
+        let calls: Vec<_> = tree.node_at(&[0]).unwrap().children().collect();
+        let sorted = |sort: TreeSort| {
+            let mut calls = calls.clone();
+            sort.sort(&mut calls, |node| std::slice::from_ref(node));
+            calls.iter().map(|node| node.func()).collect::<Vec<_>>()
+        };
+        assert_eq!(sorted(TreeSort::CallOrder), [0x20, 0x30, 0x30, 0x50]);
+        assert_eq!(sorted(TreeSort::TotalTime), [0x30, 0x30, 0x20, 0x50]);
+        assert_eq!(sorted(TreeSort::SelfTime), [0x30, 0x30, 0x20, 0x50]);
+        assert_eq!(sorted(TreeSort::Name), [0x20, 0x30, 0x30, 0x50]);
+    }
+
+    #[test]
+    fn test_fold_before_sort() {
+        let tree = FtraceTree::new(
+            String::new(),
+            vec![node(
+                0x10,
+                1000,
+                vec![
+                    node(0x30, 100, vec![]),
+                    node(0x20, 300, vec![]),
+                    node(0x30, 100, vec![]),
+                    node(0x30, 100, vec![]),
+                ],
+            )],
         );
+        let mut component = TraceTreeComponent::new(Arc::new(Config::default()));
+        component.update_tree(Arc::new(tree));
+        let ids = |component: &TraceTreeComponent| -> Vec<u64> {
+            component.data[0]
+                .children()
+                .iter()
+                .map(|item| *item.identifier())
+                .collect()
+        };
+        assert_eq!(ids(&component), [0, 1, GROUP_FLAG | 2]);
+
+        // Sorting does not fold calls made apart
+        component.sort = TreeSort::Name;
+        component.rebuild();
+        assert_eq!(ids(&component), [1, 0, GROUP_FLAG | 2]);
+
+        // Groups are sorted by the sum of their calls
+        component.sort = TreeSort::TotalTime;
+        component.rebuild();
+        assert_eq!(ids(&component), [1, GROUP_FLAG | 2, 0]);
+    }
+
+    #[test]
+    fn test_expand_hot_path() {
+        let tree = FtraceTree::new(
+            String::new(),
+            vec![node(
+                0x10,
+                1000,
+                vec![
+                    node(0x20, 500, vec![node(0x40, 450, vec![])]),
+                    node(0x30, 200, vec![]),
+                    node(0x30, 200, vec![]),
+                    node(0x50, 100, vec![]),
+                ],
+            )],
+        );
+        let mut component = TraceTreeComponent::new(Arc::new(Config::default()));
+        component.update_tree(Arc::new(tree));
+        let total = |node: &FtraceNode| node.time().unwrap_or_default();
+        let self_time = |node: &FtraceNode| node.self_time().unwrap_or_default();
+
+        component.expand_hot_path(total);
+        assert_eq!(component.state.selected(), [0, 0, 0]);
+
+        // The first of the equally hot calls, inside their group
+        component.state.select(Vec::new());
+        component.expand_hot_path(self_time);
+        assert_eq!(component.state.selected(), [0, GROUP_FLAG | 1, 1]);
+        assert_eq!(component.selected_path(), [0, 1]);
+
+        // From the selected call on
+        component.state.select(vec![0, 0]);
+        component.expand_hot_path(self_time);
+        assert_eq!(component.selected_path(), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_selected_path() {
+        let mut component = component(Some(Threshold::Time(Duration::from_nanos(10))));
+        assert_eq!(component.selected_path(), Vec::<usize>::new());
+
+        // A group stands for its first call, and its calls for themselves
+        component.state.select(vec![0, GROUP_FLAG | 1]);
+        assert_eq!(component.selected_path(), [0, 1]);
+        component.state.select(vec![0, GROUP_FLAG | 1, 2, 0]);
+        assert_eq!(component.selected_path(), [0, 2, 0]);
+
+        // The hidden calls are no node to focus
+        component.state.select(vec![0, HIDDEN_ID]);
+        assert_eq!(component.selected_path(), [0, usize::MAX]);
+        assert!(component.selected_node().is_none());
+        component.focus_selected();
+        assert!(component.focus.is_empty());
+    }
+
+    #[test]
+    fn test_focus_selected() {
+        let mut component = component(None);
+        component.state.select(vec![0, GROUP_FLAG | 1, 2]);
+        component.focus_selected();
+        assert_eq!(component.focus_path(), [0, 2]);
+        assert_eq!(component.focus_prefix(), [0]);
+        // The focused node is the root, keeping its path
+        assert_eq!(component.state.selected(), [2]);
+        assert_eq!(component.selected_path(), [0, 2]);
+        component.state.select(vec![2, 0]);
+        assert_eq!(component.selected_path(), [0, 2, 0]);
+        assert_eq!(component.selected_node().map(FtraceNode::func), Some(0x40));
+    }
+
+    #[test]
+    fn test_click_path_segment() {
+        let mut component = component(None);
+        let selected = vec![0, GROUP_FLAG | 1, 2, 0];
+        let mut click = |width: u16, column: u16| {
+            let mut terminal = Terminal::new(TestBackend::new(width, 1)).unwrap();
+            component.state.select(selected.clone());
+            terminal
+                .draw(|frame| component.render_selection_path(frame, frame.area()))
+                .unwrap();
+            component
+                .click_path_segment(Position::new(column, 0))
+                .then(|| component.state.selected().to_vec())
+        };
+
+        // Each name is 18 columns wide, between separators of 3
+        assert_eq!(click(80, 0), Some(vec![0]));
+        assert_eq!(click(80, 25), Some(vec![0, GROUP_FLAG | 1, 2]));
+        assert_eq!(click(80, 59), Some(selected.clone()));
+        assert_eq!(click(80, 19), None);
+        assert_eq!(click(80, 60), None);
+
+        // The ellipsis standing for the middle levels selects nothing
+        assert_eq!(click(50, 21), None);
+        assert_eq!(click(50, 30), Some(selected.clone()));
+    }
+
+    #[test]
+    fn test_matches_in_row_order() {
+        let mut component = component(None);
+        component.sort = TreeSort::TotalTime;
+        component.set_search("0");
         assert_eq!(
-            format_duration(Duration::from_millis(1_140_840)),
-            "1140.84 s "
+            component.matches,
+            [
+                vec![0],
+                vec![0, 1],
+                vec![0, 1, 0],
+                vec![0, 2],
+                vec![0, 2, 0],
+                vec![0, 0],
+                vec![0, 3],
+            ]
         );
-        assert_eq!(format_duration(Duration::from_secs(123_456)), " 123456 s ");
+        assert_eq!(component.selected_path(), [0]);
+        component.select_match(true);
+        assert_eq!(component.selected_path(), [0, 1]);
+        component.select_match(false);
+        component.select_match(false);
+        assert_eq!(component.selected_path(), [0, 3]);
     }
 }
@@ -0,0 +1,270 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Deserializer, de::Error as _};
+use std::{collections::HashMap, fmt, str::FromStr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Quit,
+    LoadElf,
+    ToggleInfo,
+    ToggleDetails,
+    NextView,
+    Butterfly,
+    Back,
+    ToggleAggregate,
+    Focus,
+    ShowInTree,
+    ExpandHotPath,
+    ExpandHotSelfPath,
+    ZoomIn,
+    ZoomOut,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    SortColumn,
+    ReverseSort,
+    SortAll,
+    SetThreshold,
+    Filter,
+    RaiseThreshold,
+    LowerThreshold,
+    Columns,
+    PercentBase,
+    Bookmark,
+    Bookmarks,
+    Delete,
+    Up,
+    Down,
+    Left,
+    Right,
+    First,
+    Last,
+    Toggle,
+    PageUp,
+    PageDown,
+}
+
+const DEFAULT_BINDINGS: &[(Action, &[&str])] = &[
+    (Action::Quit, &["q", "esc"]),
+    (Action::LoadElf, &["o"]),
+    (Action::ToggleInfo, &["i"]),
+    (Action::ToggleDetails, &["d"]),
+    (Action::NextView, &["tab"]),
+    (Action::Butterfly, &["b"]),
+    (Action::Back, &["backspace"]),
+    (Action::ToggleAggregate, &["a"]),
+    (Action::Focus, &["z"]),
+    (Action::ShowInTree, &["t"]),
+    (Action::ExpandHotPath, &["x"]),
+    (Action::ExpandHotSelfPath, &["X"]),
+    (Action::ZoomIn, &["+", "="]),
+    (Action::ZoomOut, &["-"]),
+    (Action::Search, &["/"]),
+    (Action::NextMatch, &["n"]),
+    (Action::PreviousMatch, &["N"]),
+    (Action::SortColumn, &["s"]),
+    (Action::ReverseSort, &["S"]),
+    (Action::SortAll, &["ctrl-s"]),
+    (Action::SetThreshold, &["%"]),
+    (Action::Filter, &["f"]),
+    (Action::RaiseThreshold, &["]"]),
+    (Action::LowerThreshold, &["["]),
+    (Action::Columns, &["c"]),
+    (Action::PercentBase, &["p"]),
+    (Action::Bookmark, &["m"]),
+    (Action::Bookmarks, &["'"]),
+    (Action::Delete, &["delete"]),
+    (Action::Up, &["up", "k"]),
+    (Action::Down, &["down", "j"]),
+    (Action::Left, &["left", "h"]),
+    (Action::Right, &["right", "l"]),
+    (Action::First, &["home", "g"]),
+    (Action::Last, &["end", "G"]),
+    (Action::Toggle, &["enter", "space"]),
+    (Action::PageUp, &["pageup"]),
+    (Action::PageDown, &["pagedown"]),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // The shift state of a character is already part of the character
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+}
+
+impl From<&KeyEvent> for KeyBinding {
+    fn from(key_event: &KeyEvent) -> Self {
+        Self::new(key_event.code, key_event.modifiers)
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::empty();
+        let mut rest = s;
+        // A lone "-" is a key, not a modifier separator
+        while let Some((modifier, key)) = rest.split_once('-')
+            && !key.is_empty()
+        {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "c" => KeyModifiers::CONTROL,
+                "alt" | "m" => KeyModifiers::ALT,
+                "shift" | "s" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier `{}` in key `{}`", modifier, s)),
+            };
+            rest = key;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "space" => KeyCode::Char(' '),
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n) => KeyCode::F(n),
+                    None => return Err(format!("unknown key `{}`", s)),
+                },
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyBinding {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => write!(f, "{}", format!("{:?}", code).to_lowercase()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct KeyMap {
+    bindings: HashMap<KeyBinding, Action>,
+}
+
+impl KeyMap {
+    pub fn action(&self, key_event: &KeyEvent) -> Option<Action> {
+        self.bindings.get(&KeyBinding::from(key_event)).copied()
+    }
+
+    /// Replace all keys bound to `action` with `keys`.
+    pub fn bind(&mut self, action: Action, keys: &[KeyBinding]) {
+        self.bindings.retain(|_, bound| *bound != action);
+        for key in keys {
+            self.bindings.insert(*key, action);
+        }
+    }
+
+    pub fn keys(&self, action: Action) -> Vec<KeyBinding> {
+        let mut keys: Vec<_> = self
+            .bindings
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(key, _)| *key)
+            .collect();
+        keys.sort_by_key(|key| key.to_string());
+        keys
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let mut bindings = HashMap::new();
+        for (action, keys) in DEFAULT_BINDINGS {
+            for key in *keys {
+                let key = key.parse().expect("Invalid default key binding");
+                bindings.insert(key, *action);
+            }
+        }
+        Self { bindings }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key_binding() {
+        assert_eq!(
+            "q".parse::<KeyBinding>(),
+            Ok(KeyBinding::new(KeyCode::Char('q'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            "ctrl-o".parse::<KeyBinding>(),
+            Ok(KeyBinding::new(KeyCode::Char('o'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            "-".parse::<KeyBinding>(),
+            Ok(KeyBinding::new(KeyCode::Char('-'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            "alt-PageDown".parse::<KeyBinding>(),
+            Ok(KeyBinding::new(KeyCode::PageDown, KeyModifiers::ALT))
+        );
+        assert_eq!(
+            "f5".parse::<KeyBinding>(),
+            Ok(KeyBinding::new(KeyCode::F(5), KeyModifiers::NONE))
+        );
+        assert!("hyper-x".parse::<KeyBinding>().is_err());
+        assert!("nokey".parse::<KeyBinding>().is_err());
+    }
+
+    #[test]
+    fn test_shifted_char_matches() {
+        let keymap = KeyMap::default();
+        let event = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(keymap.action(&event), Some(Action::Last));
+    }
+}
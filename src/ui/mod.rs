@@ -1,3 +1,5 @@
 pub mod components;
 pub mod event;
+pub mod keymap;
 pub mod term;
+pub mod theme;
@@ -4,9 +4,7 @@ use crossterm::{
 };
 use ratatui::{Terminal, prelude::CrosstermBackend};
 use std::{
-    fmt::{Debug, Formatter},
     io::{Stdout, stdout},
-    panic::PanicHookInfo,
     sync::{Arc, Mutex, Weak},
     thread,
 };
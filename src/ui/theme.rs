@@ -0,0 +1,125 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub name: Color,
+    pub time: Color,
+    pub self_time: Color,
+    pub ratio: Color,
+    pub status: Color,
+    pub regression: Color,
+    pub improvement: Color,
+    pub search_match: Color,
+    pub bookmark: Color,
+    /// Values expected but not found, such as ELF files
+    pub missing: Color,
+}
+
+impl Theme {
+    pub fn name_style(&self) -> Style {
+        Style::new().fg(self.name)
+    }
+
+    pub fn time_style(&self) -> Style {
+        Style::new().fg(self.time)
+    }
+
+    pub fn self_time_style(&self) -> Style {
+        Style::new().fg(self.self_time)
+    }
+
+    pub fn ratio_style(&self) -> Style {
+        Style::new().fg(self.ratio)
+    }
+
+    pub fn status_style(&self) -> Style {
+        Style::new().fg(self.status)
+    }
+
+    pub fn regression_style(&self) -> Style {
+        Style::new().fg(self.regression)
+    }
+
+    pub fn improvement_style(&self) -> Style {
+        Style::new().fg(self.improvement)
+    }
+
+    pub fn search_match_style(&self) -> Style {
+        Style::new().fg(Color::Black).bg(self.search_match)
+    }
+
+    pub fn bookmark_style(&self) -> Style {
+        Style::new().fg(self.bookmark)
+    }
+
+    pub fn missing_style(&self) -> Style {
+        Style::new().fg(self.missing)
+    }
+
+    pub fn header_style(&self) -> Style {
+        Style::new().add_modifier(Modifier::BOLD)
+    }
+
+    pub fn highlight_style(&self) -> Style {
+        Style::new().add_modifier(Modifier::REVERSED)
+    }
+
+    pub fn merge(&mut self, overrides: &ThemeOverrides) {
+        let ThemeOverrides {
+            name,
+            time,
+            self_time,
+            ratio,
+            status,
+            regression,
+            improvement,
+            search_match,
+            bookmark,
+            missing,
+        } = overrides;
+        self.name = name.unwrap_or(self.name);
+        self.time = time.unwrap_or(self.time);
+        self.self_time = self_time.unwrap_or(self.self_time);
+        self.ratio = ratio.unwrap_or(self.ratio);
+        self.status = status.unwrap_or(self.status);
+        self.regression = regression.unwrap_or(self.regression);
+        self.improvement = improvement.unwrap_or(self.improvement);
+        self.search_match = search_match.unwrap_or(self.search_match);
+        self.bookmark = bookmark.unwrap_or(self.bookmark);
+        self.missing = missing.unwrap_or(self.missing);
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: Color::White,
+            time: Color::Yellow,
+            self_time: Color::DarkGray,
+            ratio: Color::Blue,
+            status: Color::Gray,
+            regression: Color::LightRed,
+            improvement: Color::LightGreen,
+            search_match: Color::LightYellow,
+            bookmark: Color::LightMagenta,
+            missing: Color::Red,
+        }
+    }
+}
+
+/// Colors set by a configuration file, unset ones are inherited
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ThemeOverrides {
+    pub name: Option<Color>,
+    pub time: Option<Color>,
+    pub self_time: Option<Color>,
+    pub ratio: Option<Color>,
+    pub status: Option<Color>,
+    pub regression: Option<Color>,
+    pub improvement: Option<Color>,
+    pub search_match: Option<Color>,
+    pub bookmark: Option<Color>,
+    pub missing: Option<Color>,
+}
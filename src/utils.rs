@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, time::Duration};
 
 pub struct FormatFn<F>(F)
 where
@@ -30,3 +30,92 @@ where
         (self.0)(f)
     }
 }
+
+/// Format a duration in the unit matching its magnitude, padded to line up
+/// in columns
+pub fn format_duration(duration: Duration) -> String {
+    if duration.as_secs() >= 1000 {
+        format!("{:7} s ", duration.as_secs_f64())
+    } else if duration.as_secs() > 0 {
+        format!(
+            "{:3}.{:03} s ",
+            duration.as_secs(),
+            duration.subsec_millis()
+        )
+    } else if duration.as_millis() > 0 {
+        format!(
+            "{:3}.{:03} ms",
+            duration.as_millis(),
+            duration.subsec_micros() % 1000
+        )
+    } else if duration.as_micros() > 0 {
+        format!(
+            "{:3}.{:03} µs",
+            duration.as_micros(),
+            duration.subsec_nanos() % 1000
+        )
+    } else {
+        format!("{:7} ns", duration.as_nanos())
+    }
+}
+
+/// Format a signed difference of durations in nanoseconds, with its sign
+pub fn format_duration_diff(nanos: i128) -> String {
+    let sign = if nanos < 0 { '-' } else { '+' };
+    let duration = Duration::from_nanos(nanos.unsigned_abs().min(u64::MAX as u128) as u64);
+    format!("{}{}", sign, format_duration(duration).trim_start())
+}
+
+/// Parse a duration written as a number and a unit, e.g. `1.5us` or `20 ms`
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value: f64 = value.parse().ok()?;
+    let nanos_per_unit = match unit.trim() {
+        "ns" => 1.0,
+        "us" | "µs" => 1e3,
+        "ms" => 1e6,
+        "s" => 1e9,
+        _ => return None,
+    };
+    Some(Duration::from_nanos((value * nanos_per_unit).round() as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_nanos(123)), "    123 ns");
+        assert_eq!(format_duration(Duration::from_nanos(241_452)), "241.452 µs");
+        assert_eq!(
+            format_duration(Duration::from_nanos(343_234_431)),
+            "343.234 ms"
+        );
+        assert_eq!(
+            format_duration(Duration::from_millis(1_140_840)),
+            "1140.84 s "
+        );
+        assert_eq!(format_duration(Duration::from_secs(123_456)), " 123456 s ");
+    }
+
+    #[test]
+    fn test_format_duration_diff() {
+        assert_eq!(format_duration_diff(1_500), "+1.500 µs");
+        assert_eq!(format_duration_diff(-42), "-42 ns");
+        assert_eq!(format_duration_diff(0), "+0 ns");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("1.5us"), Some(Duration::from_nanos(1_500)));
+        assert_eq!(parse_duration("1.000 µs"), Some(Duration::from_micros(1)));
+        assert_eq!(parse_duration("20ms"), Some(Duration::from_millis(20)));
+        assert_eq!(parse_duration("42"), None);
+        assert_eq!(parse_duration("us"), None);
+    }
+}